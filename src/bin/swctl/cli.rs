@@ -1,8 +1,20 @@
 //! Command line interface for `swctl`.
 
+#[cfg(any(feature = "lss", feature = "programs"))]
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
 use clap::{Parser, Subcommand, Args};
 use stopwatchd::{
-    fmt::{DEFAULT_DATETIME_FORMAT, DEFAULT_DURATION_FORMAT},
+    communication::request_specifics::IfExists,
+    error::ParseTimeError,
+    fmt::{
+        DEFAULT_DATETIME_FORMAT,
+        DEFAULT_DURATION_FORMAT,
+        parse::{parse_adjustment, parse_auto_lap, parse_duration, parse_time}
+    },
+    identifiers::{LapRef, Tag},
+    models::{auto_lap::AutoLap, lap::TimeAdjustment},
     logging::{cli::LogLevel, DEFAULT_LOGGER_LEVEL}
 };
 
@@ -63,6 +75,20 @@ pub struct Cli {
     pub debug_ipc: bool
 }
 
+/// Parse `--at` relative to the current time.
+fn parse_at(input: &str) -> Result<SystemTime, ParseTimeError> {
+    parse_time(input, SystemTime::now())
+}
+
+/// When an action actually happened, if it wasn't just now.
+#[derive(Args, Clone, Debug)]
+pub struct AtArgs {
+    /// When this actually happened, like -5m (5 minutes ago), 14:05 or
+    /// "2023-04-01 14:05"
+    #[arg(long, value_parser = parse_at, allow_hyphen_values = true)]
+    pub at: Option<SystemTime>
+}
+
 #[derive(Subcommand, Clone, Debug)]
 pub enum Subcommands {
     /// Create and start a new stopwatch.
@@ -81,6 +107,9 @@ pub enum Subcommands {
     #[command(visible_aliases = ["l"])]
     Lap(Lap),
 
+    /// Change laps that have already been started.
+    Laps(Laps),
+
     /// Pause the current lap for each stopwatch.
     /// 
     /// Aliases: pause
@@ -93,52 +122,286 @@ pub enum Subcommands {
 
     /// Delete a stopwatch from the daemon.
     #[command(visible_aliases = ["d", "del", "remove", "rm", "r"])]
-    Delete(Delete)
+    Delete(Delete),
+
+    /// Manage countdown timers.
+    #[command(visible_aliases = ["timers"])]
+    Timer(Timer),
+
+    /// Add or remove tags from the specified stopwatches.
+    Tag(TagEdit),
+
+    /// Give a stopwatch a new name.
+    #[command(visible_aliases = ["mv"])]
+    Rename(Rename),
+
+    /// Throw away the laps of the specified stopwatches and start them again.
+    #[command(visible_aliases = ["restart"])]
+    Reset(Reset),
+
+    /// Let the specified stopwatches continue after they have been stopped.
+    #[command(visible_aliases = ["resume"])]
+    Reopen(Reopen),
+
+    /// Set how long the specified stopwatches and each of their laps should
+    /// take. Goals that are left out are removed.
+    #[command(visible_aliases = ["goals"])]
+    Goal(Goal),
+
+    /// Manage runs, lists of segments where each lap finishes the next
+    /// segment.
+    #[command(visible_aliases = ["runs"])]
+    Run(Run),
+
+    /// Time work and breaks using the pomodoro technique. Each lap is a work
+    /// or break phase and `swd` starts the next phase by itself.
+    #[command(visible_aliases = ["pomo"])]
+    Pomodoro(Pomodoro),
+
+    /// Run interval programs, like "8 x (20s work, 10s rest)". Each lap is a
+    /// step of the program and `swd` starts the next step by itself.
+    #[command(visible_aliases = ["programs", "interval"])]
+    Program(Program),
+
+    /// Create stopwatches from the attempt history of a LiveSplit splits file
+    /// (.lss). Each attempt becomes a stopwatch that has ended, with one lap
+    /// per segment.
+    #[cfg(feature = "lss")]
+    Import(Import),
+
+    /// Write the laps of a stopwatch to a LiveSplit splits file (.lss).
+    #[cfg(feature = "lss")]
+    Export(Export)
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct Start {
-    /// Name of the new stopwatch
-    pub raw_identifier: Option<String>,
+    /// Names of the new stopwatches. One stopwatch is created for each name
+    pub raw_identifiers: Vec<String>,
 
     /// Whether to fix names such that would otherwise give an error.
     #[arg(long, default_value_t=false)]
-    pub fix_bad_names: bool
+    pub fix_bad_names: bool,
+
+    /// Don't create any stopwatch if one of the names is bad or already
+    /// taken
+    #[arg(long, default_value_t=false)]
+    pub all_or_nothing: bool,
+
+    /// What to do if a stopwatch with the same name already exists
+    #[arg(long, value_enum, default_value_t=IfExists::Error)]
+    pub if_exists: IfExists,
+
+    /// Create the stopwatch without playing it, so that it can be played
+    /// later with `swctl play`
+    #[arg(long, default_value_t=false, conflicts_with = "at")]
+    pub paused: bool,
+
+    /// Tag the new stopwatch. Can be given more than once
+    #[arg(long = "tag")]
+    pub tags: Vec<Tag>,
+
+    /// Do a run that has been created with `swctl run define`. Each lap
+    /// finishes the next segment of the run
+    #[arg(long)]
+    pub run: Option<String>,
+
+    /// How long the stopwatch should take in total
+    #[arg(long, value_parser = parse_duration)]
+    pub goal: Option<Duration>,
+
+    /// How long each lap should take
+    #[arg(long, value_parser = parse_duration)]
+    pub lap_goal: Option<Duration>,
+
+    /// Start a new lap by itself, either each time the current lap has
+    /// played for a duration (like `1h`) or at the start of every hour, day
+    /// or week in local time (`hourly`, `daily` or `weekly`)
+    #[arg(long, value_parser = parse_auto_lap)]
+    pub auto_lap: Option<AutoLap>,
+
+    #[command(flatten)]
+    pub at: AtArgs
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct Info {
-    /// List of stopwatches you want information about.
+    /// List of stopwatches you want information about. Use +tag to select
+    /// every stopwatch with that tag.
     /// Leave blank to query all stopwatches
-    pub raw_identifiers: Vec<String>
+    pub raw_identifiers: Vec<String>,
+
+    /// Also show statistics about the laps that have finished
+    #[arg(long)]
+    pub stats: bool
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct Stop {
     /// List of stopwatches to stop.
     /// Must specify more than 1 stopwatch
-    pub raw_identifiers: Vec<String>
+    pub raw_identifiers: Vec<String>,
+
+    #[command(flatten)]
+    pub at: AtArgs
 }
 
 #[derive(Args, Clone, Debug)]
+#[command(args_conflicts_with_subcommands = true, disable_help_subcommand = true)]
 pub struct Lap {
+    #[command(subcommand)]
+    pub action: Option<LapAddSubcommand>,
+
     /// List stopwatches to start a new lap for.
     /// Must specify more than 1 stopwatch.
-    pub raw_identifiers: Vec<String>
+    /// Put `--` in front if the first one is called `add`.
+    pub raw_identifiers: Vec<String>,
+
+    #[command(flatten)]
+    pub annotation: LapAnnotationArgs,
+
+    /// Don't play the new lap until `swctl play` is used
+    #[arg(long, default_value_t=false, conflicts_with = "at")]
+    pub paused: bool,
+
+    #[command(flatten)]
+    pub at: AtArgs
+}
+
+/// `swctl lap add`, the same as `swctl laps add`.
+#[derive(Subcommand, Clone, Debug)]
+pub enum LapAddSubcommand {
+    /// Add a lap that was timed somewhere else.
+    Add(LapAdd)
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Laps {
+    #[command(subcommand)]
+    pub action: LapSubcommands
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum LapSubcommands {
+    /// Change the label or note of an existing lap.
+    Edit(LapEdit),
+
+    /// Add or take away time from an existing lap.
+    Adjust(LapAdjust),
+
+    /// Add a lap that was timed somewhere else.
+    Add(LapAdd),
+
+    /// Delete a lap that has finished.
+    #[command(visible_aliases = ["del", "remove", "rm"])]
+    Delete(LapTarget),
+
+    /// Merge a lap with the lap after it.
+    Merge(LapTarget),
+
+    /// Split a lap that has finished into two laps.
+    Split(LapSplit),
+
+    /// Change how long a lap has been playing for.
+    SetDuration(LapSetDuration)
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapTarget {
+    /// Stopwatch the lap belongs to
+    pub raw_identifier: String,
+
+    /// Lap number, starting from 1 for the first lap, or lap id like
+    /// @6f1ac5b2d87e (the last 12 digits of the lap's uuid)
+    pub lap: LapRef
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapSplit {
+    #[command(flatten)]
+    pub target: LapTarget,
+
+    /// How long into the lap to split it, like 90s, 10m or 25:00
+    #[arg(value_parser = parse_duration)]
+    pub offset: Duration
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapSetDuration {
+    #[command(flatten)]
+    pub target: LapTarget,
+
+    /// New duration of the lap, like 90s, 1h30m or 25:00
+    #[arg(value_parser = parse_duration)]
+    pub duration: Duration
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapAdd {
+    /// Stopwatch to add the lap to
+    pub raw_identifier: String,
+
+    /// When the lap started, like 09:00, "2023-04-01 09:00" or -2h (2 hours
+    /// ago)
+    #[arg(long, value_parser = parse_at, allow_hyphen_values = true)]
+    pub start: SystemTime,
+
+    /// How long the lap lasted, like 90s, 1h30m or 25:00
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Duration,
+
+    #[command(flatten)]
+    pub annotation: LapAnnotationArgs
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapAdjust {
+    #[command(flatten)]
+    pub target: LapTarget,
+
+    /// Time to add like +5m, or to take away like -5m
+    #[arg(value_parser = parse_adjustment, allow_hyphen_values = true)]
+    pub adjustment: TimeAdjustment
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapEdit {
+    #[command(flatten)]
+    pub target: LapTarget,
+
+    #[command(flatten)]
+    pub annotation: LapAnnotationArgs
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LapAnnotationArgs {
+    /// Label for the lap. An empty label removes it
+    #[arg(long)]
+    pub label: Option<String>,
+
+    /// Note for the lap. An empty note removes it
+    #[arg(long)]
+    pub note: Option<String>
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct Pause {
     /// List stopwatches to pause.
     /// Must specify more than 1 stopwatch.
-    pub raw_identifiers: Vec<String>
+    pub raw_identifiers: Vec<String>,
+
+    #[command(flatten)]
+    pub at: AtArgs
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct Play {
     /// List of stopwatches to play.
     /// Must specify more than 1 stopwatch.
-    pub raw_identifiers: Vec<String>
+    pub raw_identifiers: Vec<String>,
+
+    #[command(flatten)]
+    pub at: AtArgs
 }
 
 #[derive(Args, Clone, Debug)]
@@ -147,3 +410,299 @@ pub struct Delete {
     /// Must specify more than 1 stopwatch.
     pub raw_identifiers: Vec<String>
 }
+
+#[derive(Args, Clone, Debug)]
+pub struct Rename {
+    /// Stopwatch to rename
+    pub raw_identifier: String,
+
+    /// New name for the stopwatch
+    pub name: String,
+
+    /// Whether to fix names such that would otherwise give an error.
+    #[arg(long, default_value_t=false)]
+    pub fix_bad_names: bool
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Reset {
+    /// List of stopwatches to reset.
+    pub raw_identifiers: Vec<String>,
+
+    /// Keep the old laps as an archived run, shown by `info --verbose`
+    #[arg(long)]
+    pub archive: bool,
+
+    /// Don't start the new lap immediately
+    #[arg(long)]
+    pub paused: bool
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Reopen {
+    /// List of stopwatches to reopen.
+    pub raw_identifiers: Vec<String>,
+
+    /// Don't start the new lap immediately
+    #[arg(long)]
+    pub paused: bool
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TagEdit {
+    /// List of stopwatches to change the tags of.
+    pub raw_identifiers: Vec<String>,
+
+    /// Tags to add. Can be given more than once
+    #[arg(long)]
+    pub add: Vec<Tag>,
+
+    /// Tags to remove. Can be given more than once
+    #[arg(long)]
+    pub remove: Vec<Tag>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Goal {
+    /// List of stopwatches to set the goals of.
+    pub raw_identifiers: Vec<String>,
+
+    /// How long each stopwatch should take in total
+    #[arg(long, value_parser = parse_duration)]
+    pub total: Option<Duration>,
+
+    /// How long each lap should take
+    #[arg(long, value_parser = parse_duration)]
+    pub lap: Option<Duration>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Timer {
+    #[command(subcommand)]
+    pub action: TimerSubcommands
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum TimerSubcommands {
+    /// Create and start a new timer.
+    #[command(visible_aliases = ["s", "new", "n"])]
+    Start(TimerStart),
+
+    /// Get information about the specified timers.
+    #[command(visible_aliases = ["i", "get", "g"])]
+    Info(TimerTargets),
+
+    /// Pause the specified timers.
+    Pause(TimerTargets),
+
+    /// Continue counting down for the specified timers.
+    Play(TimerTargets),
+
+    /// End the specified timers before they expire.
+    #[command(visible_aliases = ["stop", "end", "e"])]
+    Cancel(TimerTargets),
+
+    /// Delete a timer from the daemon.
+    #[command(visible_aliases = ["d", "del", "remove", "rm", "r"])]
+    Delete(TimerTargets)
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TimerStart {
+    /// How long the timer should run for, like 90s, 10m, 1h30m or 25:00
+    #[arg(value_parser = parse_duration)]
+    pub target: Duration,
+
+    /// Names of the new timers. One timer is created for each name
+    pub raw_identifiers: Vec<String>,
+
+    /// Whether to fix names such that would otherwise give an error.
+    #[arg(long, default_value_t=false)]
+    pub fix_bad_names: bool
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TimerTargets {
+    /// List of timers.
+    /// Leave blank to query all timers when getting information
+    pub raw_identifiers: Vec<String>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Run {
+    #[command(subcommand)]
+    pub action: RunSubcommands
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum RunSubcommands {
+    /// Create a run, or replace the segments of an existing run with
+    /// `--replace`. Changing the segments throws away the history of the run.
+    #[command(visible_aliases = ["new", "n"])]
+    Define(RunDefine),
+
+    /// Get the personal best and golds of the specified runs.
+    #[command(visible_aliases = ["i", "get", "g"])]
+    Info(RunTargets),
+
+    /// Delete runs along with their history.
+    #[command(visible_aliases = ["d", "del", "remove", "rm", "r"])]
+    Delete(RunTargets)
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct RunDefine {
+    /// Name of the run
+    pub raw_identifier: String,
+
+    /// Names of the segments, in order
+    #[arg(required = true)]
+    pub segments: Vec<String>,
+
+    /// Replace the run if it already exists
+    #[arg(long)]
+    pub replace: bool
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct RunTargets {
+    /// List of runs.
+    /// Leave blank to query all runs when getting information
+    pub raw_identifiers: Vec<String>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Pomodoro {
+    #[command(subcommand)]
+    pub action: PomodoroSubcommands
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum PomodoroSubcommands {
+    /// Create a stopwatch in pomodoro mode, starting with a work phase.
+    #[command(visible_aliases = ["s", "new", "n"])]
+    Start(PomodoroStart),
+
+    /// End the current phase early and start the next one.
+    #[command(visible_aliases = ["next"])]
+    Skip(PomodoroTargets),
+
+    /// End the specified stopwatches in the middle of their current phase.
+    #[command(visible_aliases = ["end", "e"])]
+    Stop(PomodoroTargets)
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct PomodoroStart {
+    /// Name of the new stopwatch
+    pub raw_identifier: Option<String>,
+
+    /// Whether to fix names such that would otherwise give an error.
+    #[arg(long, default_value_t=false)]
+    pub fix_bad_names: bool,
+
+    /// Tag the new stopwatch. Can be given more than once
+    #[arg(long = "tag")]
+    pub tags: Vec<Tag>,
+
+    /// Length of each work phase
+    #[arg(long, value_parser = parse_duration, default_value = "25m")]
+    pub work: Duration,
+
+    /// Length of each short break
+    #[arg(long, value_parser = parse_duration, default_value = "5m")]
+    pub short_break: Duration,
+
+    /// Length of each long break
+    #[arg(long, value_parser = parse_duration, default_value = "15m")]
+    pub long_break: Duration,
+
+    /// Take a long break after this many work phases. 0 means never
+    #[arg(long, default_value_t = 4)]
+    pub long_break_every: usize
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct PomodoroTargets {
+    /// List of stopwatches in pomodoro mode.
+    #[arg(required = true)]
+    pub raw_identifiers: Vec<String>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct Program {
+    #[command(subcommand)]
+    pub action: ProgramSubcommands
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ProgramSubcommands {
+    /// Create a stopwatch that runs the program in a TOML file, starting
+    /// with its first step.
+    #[cfg(feature = "programs")]
+    #[command(visible_aliases = ["s", "new", "n"])]
+    Start(ProgramStart),
+
+    /// End the current step early and start the next one.
+    #[command(visible_aliases = ["next"])]
+    Skip(ProgramTargets),
+
+    /// End the specified stopwatches before their programs are completed.
+    #[command(visible_aliases = ["stop", "end", "e"])]
+    Abort(ProgramTargets)
+}
+
+#[cfg(feature = "programs")]
+#[derive(Args, Clone, Debug)]
+pub struct ProgramStart {
+    /// TOML file describing the program
+    pub path: PathBuf,
+
+    /// Name of the new stopwatch
+    pub raw_identifier: Option<String>,
+
+    /// Whether to fix names such that would otherwise give an error.
+    #[arg(long, default_value_t=false)]
+    pub fix_bad_names: bool,
+
+    /// Tag the new stopwatch. Can be given more than once
+    #[arg(long = "tag")]
+    pub tags: Vec<Tag>
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ProgramTargets {
+    /// List of stopwatches running a program.
+    #[arg(required = true)]
+    pub raw_identifiers: Vec<String>
+}
+
+#[cfg(feature = "lss")]
+#[derive(Args, Clone, Debug)]
+pub struct Import {
+    /// Splits file to import
+    pub path: PathBuf,
+
+    /// Start of the name of each stopwatch, which is followed by the id of
+    /// the attempt. Defaults to the name of the file
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Whether to fix names such that would otherwise give an error.
+    #[arg(long, default_value_t=false)]
+    pub fix_bad_names: bool,
+
+    /// Tag the new stopwatches. Can be given more than once
+    #[arg(long = "tag")]
+    pub tags: Vec<Tag>
+}
+
+#[cfg(feature = "lss")]
+#[derive(Args, Clone, Debug)]
+pub struct Export {
+    /// Stopwatch to export
+    pub raw_identifier: String,
+
+    /// Where to write the splits file
+    pub path: PathBuf
+}
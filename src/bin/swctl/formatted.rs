@@ -1,14 +1,21 @@
 //! Format the [`StopwatchDetails`] returned from `swd` into a printable format.
 
-use std::fmt;
+use std::{borrow::Cow, fmt, time::Duration};
 
 use clap::ValueEnum;
 use stopwatchd::{
-    communication::{details::StopwatchDetails, server::ServerError},
+    communication::{
+        details::{LapDelta, LapSplit, SegmentComparison, StopwatchDetails, TimerDetails},
+        server::ServerError
+    },
     fmt::Formatter,
-    models::lap::FinishedLap
+    models::{lap::FinishedLap, run::{Run, RunSegment}, stopwatch::ArchivedRun}
+};
+use tabled::{
+    Table,
+    Tabled,
+    settings::{Color, Modify, Style, object::Cell}
 };
-use tabled::{Table, Tabled, settings::Style};
 use uuid::Uuid;
 
 /// Table styles. See [`tabled`] for more information.
@@ -76,10 +83,14 @@ pub struct BasicDetails {
     #[tabled(rename = "id")] pub id: String,
     #[tabled(rename = "name")] pub name: String,
     #[tabled(rename = "state")] pub state: String,
+    #[tabled(rename = "tags")] pub tags: String,
     #[tabled(rename = "start time")] pub start_time: String,
     #[tabled(rename = "total duration")] pub total_time: String,
     #[tabled(rename = "laps count")] pub laps_count: String,
-    #[tabled(rename = "current lap time")] pub current_lap_time: String
+    #[tabled(rename = "current lap time")] pub current_lap_time: String,
+    #[tabled(rename = "goal")] pub goal: String,
+    #[tabled(rename = "lap goal")] pub lap_goal: String,
+    #[tabled(rename = "auto lap")] pub auto_lap: String
 }
 
 impl BasicDetails {
@@ -92,6 +103,7 @@ impl BasicDetails {
         let id = details.identifier.id.to_string();
         let name = details.identifier.name.to_string();
         let state = format!("{}", details.state);
+        let tags = details.tags.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
         let start_time = if show_dt {
             details.start_time
                 .map(|st| formatter.format_datetime(st))
@@ -103,7 +115,22 @@ impl BasicDetails {
         let laps_count = format!("{}", details.laps_count());
         let current_lap_time = formatter.format_duration(details.current_lap_time()
         );
-        Self { id, name, state, start_time, total_time, laps_count, current_lap_time }
+        let goal = details.goal.map(|g| g.format(formatter)).unwrap_or_default();
+        let lap_goal = details.lap_goal.map(|g| g.format(formatter)).unwrap_or_default();
+        let auto_lap = details.auto_lap.map(|a| a.to_string()).unwrap_or_default();
+        Self {
+            id,
+            name,
+            state,
+            tags,
+            start_time,
+            total_time,
+            laps_count,
+            current_lap_time,
+            goal,
+            lap_goal,
+            auto_lap
+        }
     }
 }
 
@@ -115,9 +142,13 @@ pub struct BasicDetailsNoDT {
     #[tabled(rename = "id")] pub id: String,
     #[tabled(rename = "name")] pub name: String,
     #[tabled(rename = "state")] pub state: String,
+    #[tabled(rename = "tags")] pub tags: String,
     #[tabled(rename = "total duration")] pub total_time: String,
     #[tabled(rename = "laps count")] pub laps_count: String,
-    #[tabled(rename = "current lap time")] pub current_lap_time: String
+    #[tabled(rename = "current lap time")] pub current_lap_time: String,
+    #[tabled(rename = "goal")] pub goal: String,
+    #[tabled(rename = "lap goal")] pub lap_goal: String,
+    #[tabled(rename = "auto lap")] pub auto_lap: String
 }
 
 impl From<BasicDetails> for BasicDetailsNoDT {
@@ -126,9 +157,13 @@ impl From<BasicDetails> for BasicDetailsNoDT {
             id: value.id,
             name: value.name,
             state: value.state,
+            tags: value.tags,
             total_time: value.total_time,
             laps_count: value.laps_count,
-            current_lap_time: value.current_lap_time
+            current_lap_time: value.current_lap_time,
+            goal: value.goal,
+            lap_goal: value.lap_goal,
+            auto_lap: value.auto_lap
         }
     }
 }
@@ -136,44 +171,463 @@ impl From<BasicDetails> for BasicDetailsNoDT {
 /// Verbose information about each lap from [`StopwatchDetails`].
 #[derive(Tabled, Clone, Debug, PartialEq, Eq)]
 pub struct VerboseDetails {
+    #[tabled(rename = "lap")] pub number: String,
     #[tabled(rename = "id")] pub id: String,
     #[tabled(rename = "stopwatch id")] pub stopwatch_id: String,
     #[tabled(rename = "start time")] pub start: String,
-    #[tabled(rename = "duration")] pub duration: String
+    #[tabled(rename = "duration")] pub duration: String,
+    #[tabled(rename = "split")] pub split: String,
+    #[tabled(rename = "+/- previous")] pub to_previous: String,
+    #[tabled(rename = "+/- best")] pub to_best: String,
+    #[tabled(rename = "segments")] pub segments: String,
+    #[tabled(rename = "label")] pub label: String,
+    #[tabled(rename = "note")] pub note: String
 }
 
 impl VerboseDetails {
-    /// Convert each lap into [`VerboseDetails`].
-    pub fn format(formatter: &Formatter, lap: &FinishedLap, show_dt: bool) -> Self {
+    /// Convert each lap into [`VerboseDetails`]. `number` is the position of
+    /// the lap, starting from 1.
+    pub fn format(
+        formatter: &Formatter,
+        number: usize,
+        lap: &FinishedLap,
+        split: &LapSplit,
+        show_dt: bool
+    ) -> Self {
+        let number = number.to_string();
         let id = lap.id
             .as_hyphenated()
             .encode_lower(&mut Uuid::encode_buffer())
             .to_string();
         let stopwatch_id = lap.sw_id.to_string();
-        let start = if show_dt {
-            formatter.format_datetime(lap.start)
+        let (start, segments) = if show_dt {
+            // One segment per line
+            let segments = lap.segments.iter()
+                .map(|s| format!(
+                    "{} - {}",
+                    formatter.format_datetime(s.start),
+                    formatter.format_datetime(s.end)
+                ))
+                .collect::<Vec<_>>()
+                .join("\n");
+            (formatter.format_datetime(lap.start), segments)
         } else {
-            String::new()
+            (String::new(), String::new())
         };
         let duration = formatter.format_duration(lap.duration);
-        Self { id, stopwatch_id, start, duration }
+        let delta = |delta: Option<LapDelta>| delta
+            .map(|d| d.format(formatter))
+            .unwrap_or_default();
+        let to_previous = delta(split.to_previous);
+        let to_best = delta(split.to_best);
+        let split = formatter.format_duration(split.split);
+        let label = lap.label.clone().unwrap_or_default();
+        let note = lap.note.clone().unwrap_or_default();
+        Self {
+            number,
+            id,
+            stopwatch_id,
+            start,
+            duration,
+            split,
+            to_previous,
+            to_best,
+            segments,
+            label,
+            note
+        }
+    }
+}
+
+/// Colour the "+/- previous" and "+/- best" columns of a table of
+/// [`VerboseDetails`] or [`VerboseDetailsNoDT`], green for faster laps and
+/// red for slower laps. `headers` are the headers of the table.
+pub fn color_lap_deltas(table: &mut Table, headers: &[Cow<'_, str>], splits: &[LapSplit]) {
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let columns = [
+        (column("+/- previous"), splits.iter().map(|s| s.to_previous).collect::<Vec<_>>()),
+        (column("+/- best"), splits.iter().map(|s| s.to_best).collect())
+    ];
+    for (column, deltas) in columns {
+        let column = match column {
+            Some(column) => column,
+            None => continue
+        };
+        for (index, delta) in deltas.into_iter().enumerate() {
+            let color = match delta {
+                Some(LapDelta::Faster(_)) => Color::FG_GREEN,
+                Some(LapDelta::Slower(_)) => Color::FG_RED,
+                _ => continue
+            };
+            // The first row is the header
+            table.with(Modify::new(Cell::new(index + 1, column)).with(color));
+        }
     }
 }
 
 /// [`VerboseDetails`] but with no fields containing date and time information.
 #[derive(Tabled, Clone, Debug, PartialEq, Eq)]
 pub struct VerboseDetailsNoDT {
+    #[tabled(rename = "lap")] pub number: String,
     #[tabled(rename = "id")] pub id: String,
     #[tabled(rename = "stopwatch id")] pub stopwatch_id: String,
-    #[tabled(rename = "duration")] pub duration: String
+    #[tabled(rename = "duration")] pub duration: String,
+    #[tabled(rename = "split")] pub split: String,
+    #[tabled(rename = "+/- previous")] pub to_previous: String,
+    #[tabled(rename = "+/- best")] pub to_best: String,
+    #[tabled(rename = "label")] pub label: String,
+    #[tabled(rename = "note")] pub note: String
 }
 
 impl From<VerboseDetails> for VerboseDetailsNoDT {
     fn from(value: VerboseDetails) -> Self {
         Self {
+            number: value.number,
             id: value.id,
             stopwatch_id: value.stopwatch_id,
-            duration: value.duration
+            duration: value.duration,
+            split: value.split,
+            to_previous: value.to_previous,
+            to_best: value.to_best,
+            label: value.label,
+            note: value.note
+        }
+    }
+}
+
+/// [`LapStatistics`](stopwatchd::communication::details::LapStatistics) from
+/// [`StopwatchDetails`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct LapStatsRecord {
+    #[tabled(rename = "id")] pub id: String,
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "finished laps")] pub count: String,
+    #[tabled(rename = "min")] pub min: String,
+    #[tabled(rename = "max")] pub max: String,
+    #[tabled(rename = "mean")] pub mean: String,
+    #[tabled(rename = "median")] pub median: String,
+    #[tabled(rename = "std dev")] pub std_dev: String,
+    #[tabled(rename = "fastest lap")] pub fastest: String,
+    #[tabled(rename = "slowest lap")] pub slowest: String
+}
+
+impl LapStatsRecord {
+    /// Format the lap statistics of a stopwatch. Stopwatches without any
+    /// finished laps get "none" for each statistic.
+    pub fn format(formatter: &Formatter, details: &StopwatchDetails) -> Self {
+        let id = details.identifier.id.to_string();
+        let name = details.identifier.name.to_string();
+        let none = || "none".to_string();
+        match details.lap_stats {
+            Some(ref stats) => Self {
+                id,
+                name,
+                count: stats.count.to_string(),
+                min: formatter.format_duration(stats.min),
+                max: formatter.format_duration(stats.max),
+                mean: formatter.format_duration(stats.mean),
+                median: formatter.format_duration(stats.median),
+                std_dev: formatter.format_duration(stats.std_dev),
+                fastest: stats.fastest.to_string(),
+                slowest: stats.slowest.to_string()
+            },
+            None => Self {
+                id,
+                name,
+                count: "0".to_string(),
+                min: none(),
+                max: none(),
+                mean: none(),
+                median: none(),
+                std_dev: none(),
+                fastest: none(),
+                slowest: none()
+            }
+        }
+    }
+}
+
+/// Phase of a stopwatch in pomodoro mode from [`StopwatchDetails`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct PomodoroRecord {
+    #[tabled(rename = "id")] pub id: String,
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "phase")] pub phase: String,
+    #[tabled(rename = "work phases done")] pub cycles: String,
+    #[tabled(rename = "phase time")] pub phase_time: String,
+    #[tabled(rename = "phase length")] pub phase_length: String,
+    #[tabled(rename = "remaining")] pub remaining: String
+}
+
+impl PomodoroRecord {
+    /// Format the pomodoro phase of a stopwatch. [`None`] if the stopwatch
+    /// isn't in pomodoro mode.
+    pub fn format(formatter: &Formatter, details: &StopwatchDetails) -> Option<Self> {
+        let pomodoro = details.pomodoro?;
+        let progress = details.phase_progress()?;
+        Some(Self {
+            id: details.identifier.id.to_string(),
+            name: details.identifier.name.to_string(),
+            phase: pomodoro.phase.to_string(),
+            cycles: pomodoro.cycles.to_string(),
+            phase_time: formatter.format_duration(progress.elapsed),
+            phase_length: formatter.format_duration(progress.target),
+            remaining: progress.format(formatter)
+        })
+    }
+}
+
+/// Step of a stopwatch running an interval program from [`StopwatchDetails`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct ProgramRecord {
+    #[tabled(rename = "id")] pub id: String,
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "program")] pub program: String,
+    #[tabled(rename = "step")] pub step: String,
+    #[tabled(rename = "step name")] pub step_name: String,
+    #[tabled(rename = "steps left")] pub steps_left: String,
+    #[tabled(rename = "step time")] pub step_time: String,
+    #[tabled(rename = "remaining")] pub remaining: String
+}
+
+impl ProgramRecord {
+    /// Format the program step of a stopwatch. Programs that have finished
+    /// show how they finished instead of the time remaining. [`None`] if the
+    /// stopwatch isn't running a program.
+    pub fn format(formatter: &Formatter, details: &StopwatchDetails) -> Option<Self> {
+        let progress = details.program.as_ref()?;
+        let steps = progress.program.steps.len();
+        let step = format!("{}/{}", (progress.step + 1).min(steps), steps);
+        let (step_name, step_time, remaining) = match (details.step_progress(), progress.outcome) {
+            (Some(time), _) => (
+                progress.current_step().map(|s| s.name.clone()).unwrap_or_default(),
+                formatter.format_duration(time.elapsed),
+                time.format(formatter)
+            ),
+            (None, outcome) => (
+                String::new(),
+                String::new(),
+                outcome.map(|o| o.to_string()).unwrap_or_default()
+            )
+        };
+        Some(Self {
+            id: details.identifier.id.to_string(),
+            name: details.identifier.name.to_string(),
+            program: progress.program.name.clone(),
+            step,
+            step_name,
+            steps_left: progress.remaining_steps().to_string(),
+            step_time,
+            remaining
+        })
+    }
+}
+
+/// Summary of an [`ArchivedRun`] from [`StopwatchDetails`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedRunRecord {
+    #[tabled(rename = "run")] pub number: String,
+    #[tabled(rename = "reset time")] pub reset_at: String,
+    #[tabled(rename = "laps count")] pub laps_count: String,
+    #[tabled(rename = "total duration")] pub total_time: String
+}
+
+impl ArchivedRunRecord {
+    /// Format an [`ArchivedRun`]. `number` is the position of the run,
+    /// starting from 1 for the oldest run.
+    pub fn format(formatter: &Formatter, number: usize, run: &ArchivedRun, show_dt: bool) -> Self {
+        let number = number.to_string();
+        let reset_at = if show_dt {
+            formatter.format_datetime(run.reset_at)
+        } else {
+            String::new()
+        };
+        let laps_count = run.laps.len().to_string();
+        let total_time = formatter.format_duration(run.total_time());
+        Self { number, reset_at, laps_count, total_time }
+    }
+}
+
+/// [`ArchivedRunRecord`] but with no fields containing date and time
+/// information.
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedRunRecordNoDT {
+    #[tabled(rename = "run")] pub number: String,
+    #[tabled(rename = "laps count")] pub laps_count: String,
+    #[tabled(rename = "total duration")] pub total_time: String
+}
+
+impl From<ArchivedRunRecord> for ArchivedRunRecordNoDT {
+    fn from(value: ArchivedRunRecord) -> Self {
+        Self {
+            number: value.number,
+            laps_count: value.laps_count,
+            total_time: value.total_time
+        }
+    }
+}
+
+/// Record of [`TimerDetails`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct TimerRecord {
+    #[tabled(rename = "id")] pub id: String,
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "state")] pub state: String,
+    #[tabled(rename = "start time")] pub start_time: String,
+    #[tabled(rename = "end time")] pub ended_at: String,
+    #[tabled(rename = "target")] pub target: String,
+    #[tabled(rename = "elapsed")] pub elapsed: String,
+    #[tabled(rename = "remaining")] pub remaining: String
+}
+
+impl TimerRecord {
+    /// Format [`TimerDetails`] into human-readable strings.
+    ///
+    /// # See Also
+    /// [`TimerRecordNoDT`]. Set `show_dt` to [`false`].
+    pub fn format(formatter: &Formatter, details: &TimerDetails, show_dt: bool) -> Self {
+        let id = details.identifier.id.to_string();
+        let name = details.identifier.name.to_string();
+        // Say how the timer ended rather than just "ended"
+        let state = match details.outcome {
+            Some(outcome) => outcome.to_string(),
+            None => details.state.to_string()
+        };
+        let (start_time, ended_at) = if show_dt {
+            (
+                formatter.format_datetime(details.start_time),
+                details.ended_at
+                    .map(|et| formatter.format_datetime(et))
+                    .unwrap_or("none".to_string())
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        let target = formatter.format_duration(details.target);
+        let elapsed = formatter.format_duration(details.elapsed);
+        let remaining = formatter.format_duration(details.remaining);
+        Self { id, name, state, start_time, ended_at, target, elapsed, remaining }
+    }
+}
+
+/// [`TimerRecord`] but with no fields containing date and time information.
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct TimerRecordNoDT {
+    #[tabled(rename = "id")] pub id: String,
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "state")] pub state: String,
+    #[tabled(rename = "target")] pub target: String,
+    #[tabled(rename = "elapsed")] pub elapsed: String,
+    #[tabled(rename = "remaining")] pub remaining: String
+}
+
+impl From<TimerRecord> for TimerRecordNoDT {
+    fn from(value: TimerRecord) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            state: value.state,
+            target: value.target,
+            elapsed: value.elapsed,
+            remaining: value.remaining
+        }
+    }
+}
+
+/// Summary of a [`Run`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct RunRecord {
+    #[tabled(rename = "name")] pub name: String,
+    #[tabled(rename = "segments")] pub segments: String,
+    #[tabled(rename = "attempts")] pub attempts: String,
+    #[tabled(rename = "finished")] pub completed: String,
+    #[tabled(rename = "personal best")] pub personal_best: String,
+    #[tabled(rename = "sum of best")] pub sum_of_best: String
+}
+
+impl RunRecord {
+    /// Format a [`Run`] into human-readable strings.
+    pub fn format(formatter: &Formatter, run: &Run) -> Self {
+        let duration = |d: Option<Duration>| d
+            .map(|d| formatter.format_duration(d))
+            .unwrap_or("none".to_string());
+        Self {
+            name: run.name.clone(),
+            segments: run.segments.len().to_string(),
+            attempts: run.attempts.len().to_string(),
+            completed: run.completed_attempts().to_string(),
+            personal_best: duration(run.personal_best()),
+            sum_of_best: duration(run.sum_of_best())
+        }
+    }
+}
+
+/// Times to beat for a [`RunSegment`].
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct RunSegmentRecord {
+    #[tabled(rename = "segment")] pub name: String,
+    #[tabled(rename = "personal best")] pub personal_best: String,
+    #[tabled(rename = "gold")] pub gold: String
+}
+
+impl RunSegmentRecord {
+    pub fn format(formatter: &Formatter, segment: &RunSegment) -> Self {
+        let duration = |d: Option<Duration>| d
+            .map(|d| formatter.format_duration(d))
+            .unwrap_or("none".to_string());
+        Self {
+            name: segment.name.clone(),
+            personal_best: duration(segment.personal_best),
+            gold: duration(segment.gold)
+        }
+    }
+}
+
+/// How a segment of the current attempt compares to the personal best and
+/// golds of the run.
+#[derive(Tabled, Clone, Debug, PartialEq, Eq)]
+pub struct SegmentComparisonRecord {
+    #[tabled(rename = "segment")] pub name: String,
+    #[tabled(rename = "duration")] pub time: String,
+    #[tabled(rename = "split")] pub split: String,
+    #[tabled(rename = "+/- pb")] pub to_personal_best: String,
+    #[tabled(rename = "+/- gold")] pub to_gold: String
+}
+
+impl SegmentComparisonRecord {
+    pub fn format(formatter: &Formatter, comparison: &SegmentComparison) -> Self {
+        let duration = |d: Option<Duration>| d
+            .map(|d| formatter.format_duration(d))
+            .unwrap_or_default();
+        let delta = |delta: Option<LapDelta>| delta
+            .map(|d| d.format(formatter))
+            .unwrap_or_default();
+        Self {
+            name: comparison.name.clone(),
+            time: duration(comparison.time),
+            split: duration(comparison.split),
+            to_personal_best: delta(comparison.to_personal_best),
+            to_gold: delta(comparison.to_gold)
+        }
+    }
+}
+
+/// Colour a table of [`SegmentComparisonRecord`]. Splits ahead of the
+/// personal best are green and splits behind it are red. Segments that beat
+/// their gold are yellow.
+pub fn color_segment_comparisons(table: &mut Table, comparisons: &[SegmentComparison]) {
+    // Columns of "+/- pb" and "+/- gold", the first row is the header
+    for (index, comparison) in comparisons.iter().enumerate() {
+        let pb_color = match comparison.to_personal_best {
+            Some(LapDelta::Faster(_)) => Some(Color::FG_GREEN),
+            Some(LapDelta::Slower(_)) => Some(Color::FG_RED),
+            _ => None
+        };
+        if let Some(color) = pb_color {
+            table.with(Modify::new(Cell::new(index + 1, 3)).with(color));
+        }
+        if matches!(comparison.to_gold, Some(LapDelta::Faster(_))) {
+            table.with(Modify::new(Cell::new(index + 1, 4)).with(Color::FG_YELLOW));
         }
     }
 }
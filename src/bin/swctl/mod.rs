@@ -1,12 +1,32 @@
 //! User process that talks to `swd` to interact with and get details about
 //! stopwatches.
 
-use std::process::{self, exit};
+#[cfg(feature = "lss")]
+use std::fs;
+use std::{io::{self, IsTerminal}, process::{self, exit}};
 
 #[macro_use]
 extern crate log;
 use clap::Parser;
-use formatted::{ErrorRecord, BasicDetails, BasicDetailsNoDT, VerboseDetails, VerboseDetailsNoDT};
+use formatted::{
+    ErrorRecord,
+    BasicDetails,
+    BasicDetailsNoDT,
+    VerboseDetails,
+    VerboseDetailsNoDT,
+    ArchivedRunRecord,
+    ArchivedRunRecordNoDT,
+    LapStatsRecord,
+    PomodoroRecord,
+    ProgramRecord,
+    RunRecord,
+    RunSegmentRecord,
+    SegmentComparisonRecord,
+    TimerRecord,
+    TimerRecordNoDT,
+    color_lap_deltas,
+    color_segment_comparisons
+};
 use stopwatchd::{
     fmt::Formatter,
     logging,
@@ -15,11 +35,14 @@ use stopwatchd::{
     communication::{
         client::{Request, send_request_bytes, receive_reply_bytes},
         server::{Reply, ServerError},
-        details::StopwatchDetails,
+        details::{StopwatchDetails, TimerDetails},
         reply_specifics::{SpecificAnswer, InfoAnswer}
     },
+    models::run::Run,
     traits::Codecable
 };
+#[cfg(feature = "lss")]
+use stopwatchd::lss::Splits;
 use tabled::{builder::Builder, Tabled};
 
 use crate::formatted::Styles;
@@ -80,9 +103,20 @@ async fn run(cli: cli::Cli) -> i32 {
         println!("From swd.{} to swctl.{}: {:?}", swd_pid, pid, braw);
     }
 
-    let reply = Reply::from_bytes(&braw)
+    let mut reply = Reply::from_bytes(&braw)
         .expect(&format!("could not convert message to reply"));
 
+    // Timers don't come with StopwatchDetails, so take them out first
+    let timers = match reply.specific_answer {
+        SpecificAnswer::Timer(ref mut answer) => Some(std::mem::take(&mut answer.timers)),
+        _ => None
+    };
+    // Same goes for runs
+    let runs = match reply.specific_answer {
+        SpecificAnswer::Run(ref mut answer) => Some(std::mem::take(&mut answer.runs)),
+        _ => None
+    };
+
     let (details, errors) = match reply.specific_answer {
         SpecificAnswer::Info(InfoAnswer::All(ref all)) => {
             let ao = all.access_order.clone();
@@ -91,9 +125,23 @@ async fn run(cli: cli::Cli) -> i32 {
         _ => get_details_errors(&request, reply, None)
     };
 
+    #[cfg(feature = "lss")]
+    if let cli::Subcommands::Export(ref export) = cli.action {
+        export_lss(export, &details);
+    }
+
     let formatter = Formatter::new(&cli.datetime_fmt, &cli.duration_fmt);
 
-    let good = generate_output(&cli, details, &formatter, cli.table_style);
+    let good = match (timers, runs) {
+        (Some(timers), _) => generate_timer_output(
+            &cli,
+            timers.into_iter().map(|(_, t)| t),
+            &formatter,
+            cli.table_style
+        ),
+        (_, Some(runs)) => generate_run_output(&cli, runs, &formatter, cli.table_style),
+        _ => generate_output(&cli, details, &formatter, cli.table_style)
+    };
     let bad = generate_errors(&cli, errors, &formatter, cli.table_style);
 
     if good.len() > 0 {
@@ -158,11 +206,89 @@ fn generate_output<I>(args: &cli::Cli, details: I, formatter: &Formatter, style:
 where
     I: IntoIterator<Item = StopwatchDetails>
 {
-    if args.verbose {
+    let details: Vec<StopwatchDetails> = details.into_iter().collect();
+    let stats = match args.action {
+        cli::Subcommands::Info(ref info) if info.stats => {
+            generate_stats_output(&details, formatter, style)
+        },
+        _ => String::new()
+    };
+    let pomodoros = generate_pomodoro_output(&details, formatter, style);
+    let programs = generate_program_output(&details, formatter, style);
+    let mut out = if args.verbose {
         generate_output_verbose(args, details, formatter, style)
     } else {
         generate_output_normal(args, details, formatter, style)
+    };
+    for extra in [stats, pomodoros, programs] {
+        if !extra.is_empty() {
+            out = format!("{}\n{}", out, extra);
+        }
+    }
+    out
+}
+
+/// Format the phases of stopwatches in pomodoro mode into a table. Empty if
+/// none of them are in pomodoro mode.
+fn generate_pomodoro_output(
+    details: &[StopwatchDetails],
+    formatter: &Formatter,
+    style: Styles
+) -> String {
+    let mut builder = Builder::new();
+    builder.set_header(PomodoroRecord::headers());
+    for d in details {
+        if let Some(record) = PomodoroRecord::format(formatter, d) {
+            builder.push_record(record.fields());
+        }
+    }
+    if builder.count_rows() == 0 {
+        return String::new();
+    }
+    let mut table = builder.build();
+    style.style_table(&mut table);
+    table.to_string()
+}
+
+/// Format the steps of stopwatches running a program into a table. Empty if
+/// none of them are running a program.
+fn generate_program_output(
+    details: &[StopwatchDetails],
+    formatter: &Formatter,
+    style: Styles
+) -> String {
+    let mut builder = Builder::new();
+    builder.set_header(ProgramRecord::headers());
+    for d in details {
+        if let Some(record) = ProgramRecord::format(formatter, d) {
+            builder.push_record(record.fields());
+        }
     }
+    if builder.count_rows() == 0 {
+        return String::new();
+    }
+    let mut table = builder.build();
+    style.style_table(&mut table);
+    table.to_string()
+}
+
+/// Format the lap statistics in [`StopwatchDetails`] into a table.
+fn generate_stats_output(
+    details: &[StopwatchDetails],
+    formatter: &Formatter,
+    style: Styles
+) -> String {
+    if details.is_empty() {
+        return String::new();
+    }
+    let mut builder = Builder::new();
+    builder.set_header(LapStatsRecord::headers());
+    for d in details {
+        builder.push_record(LapStatsRecord::format(formatter, d).fields());
+    }
+    let mut table = builder.build();
+    style.style_table(&mut table);
+    table.to_string()
 }
 
 fn generate_output_normal<I>(
@@ -206,6 +332,8 @@ fn generate_output_verbose<I>(
 where
     I: IntoIterator<Item = StopwatchDetails>
 {
+    // Don't print colour codes into files and pipes
+    let color = io::stdout().is_terminal();
     let mut n_stopwatches: usize = 0;
     let mut out = "+++\n".to_string();
     'l: for d in details {
@@ -223,19 +351,27 @@ where
         style.style_table(&mut table);
         out.push_str(&table.to_string());
 
-        let verbose = match d.verbose_info {
-            Some(v) => v,
-            None => continue 'l
+        let comparisons = d.run_comparisons();
+        let (verbose, splits) = match (d.lap_splits(), d.verbose_info) {
+            (Some(s), Some(v)) => (v, s),
+            _ => continue 'l
         };
         out.push_str("\n---\n");
         let mut verbose_builder = Builder::default();
-        if args.show_datetime_info {
-            verbose_builder.set_header(VerboseDetails::headers());
+        let headers = if args.show_datetime_info {
+            VerboseDetails::headers()
         } else {
-            verbose_builder.set_header(VerboseDetailsNoDT::headers());
-        }
-        for lap in &verbose.laps {
-            let vd = VerboseDetails::format(formatter, lap, args.show_datetime_info);
+            VerboseDetailsNoDT::headers()
+        };
+        verbose_builder.set_header(headers.clone());
+        for (index, (lap, split)) in verbose.laps.iter().zip(&splits).enumerate() {
+            let vd = VerboseDetails::format(
+                formatter,
+                index + 1,
+                lap,
+                split,
+                args.show_datetime_info
+            );
             if args.show_datetime_info {
                 verbose_builder.push_record(vd.fields());
             } else {
@@ -244,8 +380,52 @@ where
         }
         let mut table = verbose_builder.build();
         style.style_table(&mut table);
+        if color {
+            color_lap_deltas(&mut table, &headers, &splits);
+        }
         out.push_str(&table.to_string());
 
+        if let Some(comparisons) = comparisons {
+            out.push_str("\n---\n");
+            let mut run_builder = Builder::default();
+            run_builder.set_header(SegmentComparisonRecord::headers());
+            for comparison in &comparisons {
+                run_builder.push_record(SegmentComparisonRecord::format(formatter, comparison).fields());
+            }
+            let mut table = run_builder.build();
+            style.style_table(&mut table);
+            if color {
+                color_segment_comparisons(&mut table, &comparisons);
+            }
+            out.push_str(&table.to_string());
+        }
+
+        if !verbose.archived_runs.is_empty() {
+            out.push_str("\n---\n");
+            let mut archive_builder = Builder::default();
+            if args.show_datetime_info {
+                archive_builder.set_header(ArchivedRunRecord::headers());
+            } else {
+                archive_builder.set_header(ArchivedRunRecordNoDT::headers());
+            }
+            for (index, run) in verbose.archived_runs.iter().enumerate() {
+                let record = ArchivedRunRecord::format(
+                    formatter,
+                    index + 1,
+                    run,
+                    args.show_datetime_info
+                );
+                if args.show_datetime_info {
+                    archive_builder.push_record(record.fields());
+                } else {
+                    archive_builder.push_record(ArchivedRunRecordNoDT::from(record).fields());
+                }
+            }
+            let mut table = archive_builder.build();
+            style.style_table(&mut table);
+            out.push_str(&table.to_string());
+        }
+
         out.push_str("\n+++");
     }
     if n_stopwatches == 0 {
@@ -255,6 +435,82 @@ where
     }
 }
 
+/// Format [`TimerDetails`] into a string.
+fn generate_timer_output<I>(
+    args: &cli::Cli,
+    timers: I,
+    formatter: &Formatter,
+    style: Styles
+) -> String
+where
+    I: IntoIterator<Item = TimerDetails>
+{
+    let mut builder = Builder::new();
+    if args.show_datetime_info {
+        builder.set_header(TimerRecord::headers());
+    } else {
+        builder.set_header(TimerRecordNoDT::headers());
+    }
+    for t in timers {
+        let record = TimerRecord::format(formatter, &t, args.show_datetime_info);
+        if args.show_datetime_info {
+            builder.push_record(record.fields());
+        } else {
+            builder.push_record(TimerRecordNoDT::from(record).fields());
+        }
+    }
+    if builder.count_rows() == 0 {
+        "".to_string()
+    } else {
+        let mut table = builder.build();
+        style.style_table(&mut table);
+        table.to_string()
+    }
+}
+
+/// Format [`Run`]s into a string. With `--verbose`, the personal best and gold
+/// of each segment are shown as well.
+fn generate_run_output(args: &cli::Cli, runs: Vec<Run>, formatter: &Formatter, style: Styles) -> String {
+    if runs.is_empty() {
+        return String::new();
+    }
+    let mut builder = Builder::new();
+    builder.set_header(RunRecord::headers());
+    for run in &runs {
+        builder.push_record(RunRecord::format(formatter, run).fields());
+    }
+    let mut table = builder.build();
+    style.style_table(&mut table);
+    let mut out = table.to_string();
+    if args.verbose {
+        for run in &runs {
+            out.push_str(&format!("\n--- {}\n", run.name));
+            let mut segment_builder = Builder::new();
+            segment_builder.set_header(RunSegmentRecord::headers());
+            for segment in &run.segments {
+                segment_builder.push_record(RunSegmentRecord::format(formatter, segment).fields());
+            }
+            let mut table = segment_builder.build();
+            style.style_table(&mut table);
+            out.push_str(&table.to_string());
+        }
+    }
+    out
+}
+
+/// Write every lap of the stopwatch in `details` to a LiveSplit splits file.
+#[cfg(feature = "lss")]
+fn export_lss(export: &cli::Export, details: &[StopwatchDetails]) {
+    let (d, verbose) = match details.first() {
+        Some(d @ StopwatchDetails { verbose_info: Some(verbose), .. }) => (d, verbose),
+        _ => return
+    };
+    let splits = Splits::from_laps(d.get_raw_id(), &verbose.laps);
+    fs::write(&export.path, splits.to_xml())
+        .unwrap_or_else(|e| panic!("could not write to {:?}: {}", export.path, e));
+    info!("exported {} laps to {:?}", verbose.laps.len(), export.path);
+}
+
 /// Format [`ServerError`] into strings.
 fn generate_errors<I>(_args: &cli::Cli, iter: I, formatter: &Formatter, style: Styles) -> String
 where
@@ -1,29 +1,279 @@
 //! Convert command line arguments to a request to `swd`.
 
+#[cfg(any(feature = "lss", feature = "programs"))]
+use std::fs;
+#[cfg(feature = "lss")]
+use std::time::SystemTime;
+
+#[cfg(feature = "lss")]
+use stopwatchd::{
+    communication::request_specifics::{ImportArgs, ImportedStopwatch},
+    lss::Splits
+};
+#[cfg(feature = "programs")]
+use stopwatchd::models::program::ProgramDefinition;
 use stopwatchd::{
     communication::{
-        client::{Request, CommonArgs},
-        request_specifics::{StartArgs, InfoArgs, StopArgs, LapArgs, PauseArgs, PlayArgs, DeleteArgs}
-    }
+        client::{Request, CommonArgs, SpecificArgs},
+        request_specifics::{
+            StartArgs, InfoArgs, StopArgs, LapArgs, PauseArgs, PlayArgs, DeleteArgs, TimerArgs,
+            TimerAction, EditLapArgs, TagArgs, RenameArgs, ResetArgs, ReopenArgs, AdjustLapArgs,
+            AddLapArgs, ModifyLapArgs, LapAction, RunArgs, RunAction, GoalArgs, PomodoroArgs,
+            PomodoroAction, ProgramArgs, ProgramAction
+        }
+    },
+    models::{lap::LapAnnotation, pomodoro::PomodoroConfig, stopwatch::Goals}
 };
 
-use crate::cli::{self, Subcommands};
+use crate::cli::{
+    self,
+    Subcommands,
+    TimerSubcommands,
+    RunSubcommands,
+    PomodoroSubcommands,
+    ProgramSubcommands,
+    LapSubcommands,
+    LapAddSubcommand,
+    LapAdd,
+    LapAnnotationArgs,
+    LapTarget
+};
 
 /// Convert arguments to a request. See [`Request`] on how to send
 /// a serialised message to `swd`.
 pub fn args_to_request(args: &cli::Cli) -> Request {
     let (identifiers, specific) = match &args.action {
         Subcommands::Start(args) => (
-            args.raw_identifier.iter().map(Clone::clone).collect(),
-            StartArgs { fix_bad_names: args.fix_bad_names }.into()
+            args.raw_identifiers.clone(),
+            StartArgs {
+                fix_bad_names: args.fix_bad_names,
+                all_or_nothing: args.all_or_nothing,
+                if_exists: args.if_exists,
+                paused: args.paused,
+                tags: args.tags.clone(),
+                at: args.at.at,
+                run: args.run.clone(),
+                goals: Goals { total: args.goal, lap: args.lap_goal },
+                pomodoro: None,
+                program: None,
+                auto_lap: args.auto_lap
+            }.into()
         ),
         Subcommands::Info(args) => (args.raw_identifiers.clone(), InfoArgs.into()),
-        Subcommands::Stop(args) => (args.raw_identifiers.clone(), StopArgs.into()),
-        Subcommands::Lap(args) => (args.raw_identifiers.clone(), LapArgs.into()),
-        Subcommands::Pause(args) => (args.raw_identifiers.clone(), PauseArgs.into()),
-        Subcommands::Play(args) => (args.raw_identifiers.clone(), PlayArgs.into()),
-        Subcommands::Delete(args) => (args.raw_identifiers.clone(), DeleteArgs.into())
+        Subcommands::Stop(args) => (
+            args.raw_identifiers.clone(),
+            StopArgs { at: args.at.at }.into()
+        ),
+        Subcommands::Lap(args) => match &args.action {
+            Some(LapAddSubcommand::Add(add)) => add_lap(add),
+            None => (
+                args.raw_identifiers.clone(),
+                LapArgs {
+                    annotation: annotation(&args.annotation),
+                    at: args.at.at,
+                    paused: args.paused
+                }.into()
+            )
+        },
+        Subcommands::Laps(args) => match &args.action {
+            LapSubcommands::Edit(edit) => (
+                vec![edit.target.raw_identifier.clone()],
+                EditLapArgs { lap: edit.target.lap, annotation: annotation(&edit.annotation) }.into()
+            ),
+            LapSubcommands::Adjust(adjust) => (
+                vec![adjust.target.raw_identifier.clone()],
+                AdjustLapArgs { lap: adjust.target.lap, adjustment: adjust.adjustment }.into()
+            ),
+            LapSubcommands::Add(add) => add_lap(add),
+            LapSubcommands::Delete(target) => modify_lap(target, LapAction::Delete),
+            LapSubcommands::Merge(target) => modify_lap(target, LapAction::Merge),
+            LapSubcommands::Split(split) => {
+                modify_lap(&split.target, LapAction::Split { offset: split.offset })
+            },
+            LapSubcommands::SetDuration(set) => {
+                modify_lap(&set.target, LapAction::SetDuration { duration: set.duration })
+            }
+        },
+        Subcommands::Pause(args) => (
+            args.raw_identifiers.clone(),
+            PauseArgs { at: args.at.at }.into()
+        ),
+        Subcommands::Play(args) => (
+            args.raw_identifiers.clone(),
+            PlayArgs { at: args.at.at }.into()
+        ),
+        Subcommands::Delete(args) => (args.raw_identifiers.clone(), DeleteArgs.into()),
+        Subcommands::Timer(args) => timer_args(&args.action),
+        Subcommands::Tag(args) => (
+            args.raw_identifiers.clone(),
+            TagArgs { add: args.add.clone(), remove: args.remove.clone() }.into()
+        ),
+        Subcommands::Rename(args) => (
+            vec![args.raw_identifier.clone()],
+            RenameArgs { name: args.name.clone(), fix_bad_names: args.fix_bad_names }.into()
+        ),
+        Subcommands::Reset(args) => (
+            args.raw_identifiers.clone(),
+            ResetArgs { archive: args.archive, paused: args.paused }.into()
+        ),
+        Subcommands::Reopen(args) => (
+            args.raw_identifiers.clone(),
+            ReopenArgs { paused: args.paused }.into()
+        ),
+        Subcommands::Goal(args) => (
+            args.raw_identifiers.clone(),
+            GoalArgs { goals: Goals { total: args.total, lap: args.lap } }.into()
+        ),
+        Subcommands::Run(args) => run_args(&args.action),
+        Subcommands::Pomodoro(args) => pomodoro_args(&args.action),
+        Subcommands::Program(args) => program_args(&args.action),
+        #[cfg(feature = "lss")]
+        Subcommands::Import(args) => import_args(args),
+        // Exporting needs every lap of the stopwatch, see `export_lss`
+        #[cfg(feature = "lss")]
+        Subcommands::Export(args) => (vec![args.raw_identifier.clone()], InfoArgs.into())
     };
-    let common = CommonArgs::from_iter(identifiers, args.verbose);
+    #[cfg(feature = "lss")]
+    let verbose = args.verbose || matches!(args.action, Subcommands::Export(_));
+    #[cfg(not(feature = "lss"))]
+    let verbose = args.verbose;
+    let common = CommonArgs::from_iter(identifiers, verbose);
     Request::new(common, specific)
-}
\ No newline at end of file
+}
+/// Identifiers and [`TimerArgs`] for `swctl timer`.
+fn timer_args(action: &TimerSubcommands) -> (Vec<String>, SpecificArgs) {
+    let (identifiers, action) = match action {
+        TimerSubcommands::Start(args) => (
+            args.raw_identifiers.clone(),
+            TimerAction::Start { target: args.target, fix_bad_names: args.fix_bad_names }
+        ),
+        TimerSubcommands::Info(args) => (args.raw_identifiers.clone(), TimerAction::Info),
+        TimerSubcommands::Pause(args) => (args.raw_identifiers.clone(), TimerAction::Pause),
+        TimerSubcommands::Play(args) => (args.raw_identifiers.clone(), TimerAction::Play),
+        TimerSubcommands::Cancel(args) => (args.raw_identifiers.clone(), TimerAction::Cancel),
+        TimerSubcommands::Delete(args) => (args.raw_identifiers.clone(), TimerAction::Delete)
+    };
+    (identifiers, TimerArgs { action }.into())
+}
+
+/// Identifiers and [`RunArgs`] for `swctl run`.
+fn run_args(action: &RunSubcommands) -> (Vec<String>, SpecificArgs) {
+    let (identifiers, action) = match action {
+        RunSubcommands::Define(args) => (
+            vec![args.raw_identifier.clone()],
+            RunAction::Define { segments: args.segments.clone(), replace: args.replace }
+        ),
+        RunSubcommands::Info(args) => (args.raw_identifiers.clone(), RunAction::Info),
+        RunSubcommands::Delete(args) => (args.raw_identifiers.clone(), RunAction::Delete)
+    };
+    (identifiers, RunArgs { action }.into())
+}
+
+/// Identifiers and [`StartArgs`] or [`PomodoroArgs`] for `swctl pomodoro`.
+fn pomodoro_args(action: &PomodoroSubcommands) -> (Vec<String>, SpecificArgs) {
+    let (identifiers, action) = match action {
+        PomodoroSubcommands::Start(args) => {
+            let config = PomodoroConfig {
+                work: args.work,
+                short_break: args.short_break,
+                long_break: args.long_break,
+                long_break_every: args.long_break_every
+            };
+            let start = StartArgs {
+                fix_bad_names: args.fix_bad_names,
+                tags: args.tags.clone(),
+                pomodoro: Some(config),
+                ..Default::default()
+            };
+            return (args.raw_identifier.iter().cloned().collect(), start.into());
+        },
+        PomodoroSubcommands::Skip(args) => (args.raw_identifiers.clone(), PomodoroAction::Skip),
+        PomodoroSubcommands::Stop(args) => (args.raw_identifiers.clone(), PomodoroAction::Stop)
+    };
+    (identifiers, PomodoroArgs { action }.into())
+}
+
+/// Identifiers and [`StartArgs`] or [`ProgramArgs`] for `swctl program`.
+fn program_args(action: &ProgramSubcommands) -> (Vec<String>, SpecificArgs) {
+    let (identifiers, action) = match action {
+        #[cfg(feature = "programs")]
+        ProgramSubcommands::Start(args) => {
+            let toml = fs::read_to_string(&args.path)
+                .unwrap_or_else(|e| panic!("could not read {:?}: {}", args.path, e));
+            let stem = args.path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let program = ProgramDefinition::from_toml(&toml)
+                .and_then(|definition| definition.expand(&stem))
+                .unwrap_or_else(|e| panic!("could not load {:?}: {}", args.path, e));
+            let start = StartArgs {
+                fix_bad_names: args.fix_bad_names,
+                tags: args.tags.clone(),
+                program: Some(program),
+                ..Default::default()
+            };
+            return (args.raw_identifier.iter().cloned().collect(), start.into());
+        },
+        ProgramSubcommands::Skip(args) => (args.raw_identifiers.clone(), ProgramAction::Skip),
+        ProgramSubcommands::Abort(args) => (args.raw_identifiers.clone(), ProgramAction::Abort)
+    };
+    (identifiers, ProgramArgs { action }.into())
+}
+
+/// Identifiers and [`ImportArgs`] for `swctl import`. Attempts that were reset
+/// before finishing the first segment are left out.
+#[cfg(feature = "lss")]
+fn import_args(args: &cli::Import) -> (Vec<String>, SpecificArgs) {
+    let xml = fs::read_to_string(&args.path)
+        .unwrap_or_else(|e| panic!("could not read {:?}: {}", args.path, e));
+    let splits = Splits::parse(&xml)
+        .unwrap_or_else(|e| panic!("could not import {:?}: {}", args.path, e));
+    let prefix = match args.name {
+        Some(ref name) => name.clone(),
+        None => args.path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let mut identifiers = vec![];
+    let mut stopwatches = vec![];
+    for attempt in &splits.attempts {
+        let laps = attempt.laps(&splits.segments);
+        if laps.is_empty() {
+            continue;
+        }
+        // Pretend that attempts without a start time just finished. If that
+        // would be too long ago, swd refuses the laps for ending in the future
+        let now = SystemTime::now();
+        let start = attempt.started
+            .or_else(|| attempt.total_time().and_then(|total| now.checked_sub(total)))
+            .unwrap_or(now);
+        identifiers.push(format!("{}-{}", prefix, attempt.id));
+        stopwatches.push(ImportedStopwatch { start, laps });
+    }
+    let import = ImportArgs {
+        fix_bad_names: args.fix_bad_names,
+        tags: args.tags.clone(),
+        stopwatches
+    };
+    (identifiers, import.into())
+}
+
+fn annotation(args: &LapAnnotationArgs) -> LapAnnotation {
+    LapAnnotation { label: args.label.clone(), note: args.note.clone() }
+}
+
+/// Identifiers and [`AddLapArgs`] for `swctl lap add` and `swctl laps add`.
+fn add_lap(add: &LapAdd) -> (Vec<String>, SpecificArgs) {
+    let args = AddLapArgs {
+        start: add.start,
+        duration: add.duration,
+        annotation: annotation(&add.annotation)
+    };
+    (vec![add.raw_identifier.clone()], args.into())
+}
+
+/// Identifiers and [`ModifyLapArgs`] for `swctl laps delete`, `merge`,
+/// `split` and `set-duration`.
+fn modify_lap(target: &LapTarget, action: LapAction) -> (Vec<String>, SpecificArgs) {
+    (vec![target.raw_identifier.clone()], ModifyLapArgs { lap: target.lap, action }.into())
+}
@@ -1,9 +1,9 @@
 //! Configuration and command line arguments for `swd`.
 
+use std::path::PathBuf;
 #[cfg(feature = "swd-config")]
 use std::{
     io::{self, Read},
-    path::PathBuf,
     str::FromStr,
     fs::OpenOptions
 };
@@ -12,8 +12,18 @@ use std::env;
 
 use clap::Parser;
 #[cfg(feature = "swd-config")]
+use clap::ValueEnum;
+#[cfg(feature = "swd-config")]
 use log::LevelFilter;
-use stopwatchd::logging::{DEFAULT_LOGGER_LEVEL, cli::LogLevel};
+#[cfg(feature = "swd-config")]
+use stopwatchd::identifiers::{NameChars, Normalization};
+use stopwatchd::{
+    identifiers::NamePolicy,
+    logging::{DEFAULT_LOGGER_LEVEL, cli::LogLevel},
+    runtime::data_dir
+};
+
+use crate::manager::ManagerConfig;
 #[cfg(feature = "swd-config")]
 use toml::{Table, Value};
 #[cfg(feature = "users")]
@@ -66,6 +76,25 @@ pub struct Cli {
     #[arg(short, long, value_enum, help = "Set log level")]
     pub log_level: Option<LogLevel>,
 
+    /// Directory where stopwatches are saved so that they survive restarts.
+    #[arg(long = "data-dir", help = "Set data directory")]
+    pub data_dir: Option<String>,
+
+    /// Whether stopwatches that have ended can be reopened. Only set by the
+    /// config file.
+    #[arg(skip)]
+    pub allow_reopen: Option<bool>,
+
+    /// Template for the names of stopwatches started without a name. Only
+    /// set by the config file.
+    #[arg(skip)]
+    pub name_template: Option<String>,
+
+    /// Rules for names of new stopwatches and timers. Only set by the config
+    /// file.
+    #[arg(skip)]
+    pub name_policy: Option<NamePolicy>,
+
     /// Path to config file.
     #[cfg(feature = "swd-config")]
     #[arg(
@@ -82,6 +111,25 @@ impl Cli {
         self.log_level.unwrap_or(DEFAULT_LOGGER_LEVEL.into())
     }
 
+    /// Get the data directory, falling back to [`data_dir`] for user `uid`.
+    pub fn data_dir(&self, uid: Option<u32>) -> PathBuf {
+        match self.data_dir {
+            Some(ref d) => PathBuf::from(d),
+            None => data_dir(uid)
+        }
+    }
+
+    /// Options for [`Manager`](crate::manager::Manager) that can change when
+    /// the config file is reloaded.
+    pub fn manager_config(&self) -> ManagerConfig {
+        let default = ManagerConfig::default();
+        ManagerConfig {
+            allow_reopen: self.allow_reopen.unwrap_or(default.allow_reopen),
+            name_template: self.name_template.clone().or(default.name_template),
+            name_policy: self.name_policy.unwrap_or(default.name_policy)
+        }
+    }
+
     /// Combine command line arguments stored in `self`
     /// with options in TOML [`Table`].
     #[cfg(feature = "swd-config")]
@@ -98,6 +146,44 @@ impl Cli {
                 ))
             };
         }
+        if self.data_dir.is_none() {
+            self.data_dir = match table.get("data_dir") {
+                Some(Value::String(s)) => Some(s.clone()),
+                None => None,
+                _ => return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "data_dir in config file must be a string"
+                ))
+            };
+        }
+        self.allow_reopen = match table.get("allow_reopen") {
+            Some(Value::Boolean(b)) => Some(*b),
+            None => None,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "allow_reopen in config file must be a boolean"
+            ))
+        };
+        let name_policy = name_policy_from_toml(&table)?;
+        self.name_template = match table.get("name_template") {
+            Some(Value::String(s)) if s.contains("{n}") => Some(s.clone()),
+            None => None,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "name_template in config file must be a string containing {n}"
+            ))
+        };
+        if let Some(ref template) = self.name_template {
+            // Any date has the same length
+            let sample = template.replace("{date}", "2000-01-01");
+            if let Err(e) = name_policy.numbered(&sample, 1) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("name_template in config file gives bad names: {}", e)
+                ));
+            }
+        }
+        self.name_policy = Some(name_policy);
         Ok(self)
     }
 
@@ -129,15 +215,63 @@ impl Cli {
     }
 }
 
+/// Read the `name_*` options of the config file. Options that aren't set
+/// are taken from [`NamePolicy::default`].
+#[cfg(feature = "swd-config")]
+fn name_policy_from_toml(table: &Table) -> Result<NamePolicy, io::Error> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let mut policy = NamePolicy::default();
+    match table.get("name_chars") {
+        Some(Value::String(s)) => policy.chars = NameChars::from_str(s, true)
+            .map_err(|_| invalid(format!("config file error: unknown name_chars {:?}", s)))?,
+        None => {},
+        _ => return Err(invalid("name_chars in config file must be a string".to_string()))
+    }
+    match table.get("name_max_length") {
+        Some(Value::Integer(i)) if *i >= 0 => policy.max_length = *i as usize,
+        None => {},
+        _ => return Err(invalid(
+            "name_max_length in config file must be a non-negative integer".to_string()
+        ))
+    }
+    match table.get("name_trim") {
+        Some(Value::Boolean(b)) => policy.trim = *b,
+        None => {},
+        _ => return Err(invalid("name_trim in config file must be a boolean".to_string()))
+    }
+    match table.get("name_normalization") {
+        Some(Value::String(s)) => policy.normalization = Normalization::from_str(s, true)
+            .map_err(|_| invalid(format!("config file error: unknown normalization {:?}", s)))?,
+        None => {},
+        _ => return Err(invalid(
+            "name_normalization in config file must be a string".to_string()
+        ))
+    }
+    Ok(policy)
+}
+
 impl Default for Cli {
     fn default() -> Self {
         // `return` because rust doesn't know how to deal with expressions with
         // cfg yet
 
         #[cfg(not(feature = "swd-config"))]
-        return Self { log_level: None };
+        return Self {
+            log_level: None,
+            data_dir: None,
+            allow_reopen: None,
+            name_template: None,
+            name_policy: None
+        };
 
         #[cfg(feature = "swd-config")]
-        return Self { log_level: None, config_path: SYSTEM_CONFIG_PATH.to_string() };
+        return Self {
+            log_level: None,
+            data_dir: None,
+            allow_reopen: None,
+            name_template: None,
+            name_policy: None,
+            config_path: SYSTEM_CONFIG_PATH.to_string()
+        };
     }
 }
\ No newline at end of file
@@ -1,25 +1,52 @@
 //! Manages the stopwatch.
 
 use std::{
-    collections::{HashMap, hash_map::Entry},
-    ops
+    collections::{BTreeMap, HashMap, HashSet, hash_map::Entry},
+    io,
+    ops,
+    path::PathBuf,
+    time::Duration
 };
 
+use chrono::Local;
+use serde::{Serialize, Deserialize};
 use stopwatchd::{
     communication::{
         client::Request,
         server::{Reply, ServerError},
         reply_specifics::*,
-        details::StopwatchDetails,
-        request_specifics::SpecificArgs
+        details::{StopwatchDetails, TimerDetails},
+        request_specifics::{
+            SpecificArgs, TimerAction, LapArgs, EditLapArgs, TagArgs, RenameArgs, ResetArgs, ReopenArgs,
+            StopArgs, PlayArgs, PauseArgs, AdjustLapArgs, AddLapArgs, ModifyLapArgs, LapAction,
+            RunAction, ImportArgs, GoalArgs, PomodoroArgs, PomodoroAction, ProgramArgs,
+            ProgramAction, StartArgs, IfExists, TimerArgs, RunArgs
+        }
+    },
+    models::{
+        run::{Run, RunAttempt, RunProgress},
+        stopwatch::{Stopwatch, State},
+        timer::Timer
+    },
+    error::{FindStopwatchError, FindTimerError, InvalidState, InvalidLap, LapError},
+    identifiers::{Identifier, UniqueId, Name, NamePolicy, Tag}
+};
+use tokio::{
+    sync::{
+        mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel},
+        watch
     },
-    models::stopwatch::{Stopwatch, State},
-    error::{FindStopwatchError, InvalidState},
-    identifiers::{Identifier, UniqueId, Name}
+    task::{spawn_blocking, JoinHandle},
+    time::{interval, MissedTickBehavior}
 };
-use tokio::sync::mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel};
 
-use crate::raw_identifier::{RawIdentifier, IdentifierMatch};
+use crate::{
+    raw_identifier::{RawIdentifier, IdentifierMatch},
+    storage::{
+        SavedState, SavedStateRef, load_state, save_state, encode_state, write_state,
+        quarantine_state
+    }
+};
 
 #[derive(Clone, Debug)]
 pub struct JobRequest {
@@ -37,6 +64,33 @@ pub type JobReceiver = UnboundedReceiver<JobRequest>;
 pub type ResponseSender = UnboundedSender<JobResponse>;
 pub type ResponseReceiver = UnboundedReceiver<JobResponse>;
 
+#[cfg(feature = "swd-config")]
+pub type ConfigSender = watch::Sender<ManagerConfig>;
+pub type ConfigReceiver = watch::Receiver<ManagerConfig>;
+
+/// Options from the config file that [`manage`] follows. A new
+/// [`ManagerConfig`] is sent through a [`ConfigSender`] whenever the config
+/// file is reloaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManagerConfig {
+    /// Whether stopwatches that have ended can be reopened.
+    pub allow_reopen: bool,
+    /// Template for the names of stopwatches started without a name. See
+    /// [`NameRegistry::generate_name`]. If [`None`], they are left unnamed.
+    pub name_template: Option<String>,
+    /// Rules for the names of new stopwatches and timers.
+    pub name_policy: NamePolicy
+}
+
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        Self { allow_reopen: true, name_template: None, name_policy: NamePolicy::default() }
+    }
+}
+
+/// How often [`manage`] checks whether any [`Timer`] has expired.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Create channels to send requests to [`Manager`].
 #[inline]
 pub fn make_request_channels() -> (JobSender, JobReceiver) {
@@ -57,22 +111,46 @@ pub type ActionTakeStopwatch = fn(&mut ActionState, String, Option<Stopwatch>);
 /// State that gets passed to any of the "Action" functions
 pub struct ActionState<'rq> {
     pub reply: Reply,
-    pub request: &'rq Request
+    pub request: &'rq Request,
+    /// Attempts at runs that finished or were given up on, along with the
+    /// name of the run. [`manage`] records them once the action is done.
+    pub attempts: Vec<(String, RunAttempt)>
 }
 
 impl<'rq> ActionState<'rq> {
     pub fn new(reply: Reply, request: &'rq Request) -> Self {
-        Self { reply, request }
+        Self { reply, request, attempts: vec![] }
     }
 }
 
+/// Goals of a [`Stopwatch`] that have already been reached, so that each goal
+/// is only announced once.
+#[derive(Clone, Copy, Debug, Default)]
+struct ReachedGoals {
+    total: bool,
+    /// Lap number of the lap that reached the lap goal.
+    lap: Option<usize>
+}
+
 /// Contains [`Stopwatch`]es and auxiliary info.
 /// 
 /// Use [`manage`] to run the manager.
 pub struct Manager {
     stopwatches: HashMap<UniqueId, Stopwatch>,
     access_order: AccessOrder,
-    name_registry: NameRegistry
+    name_registry: NameRegistry,
+    timers: HashMap<UniqueId, Timer>,
+    timer_access_order: AccessOrder,
+    timer_name_registry: NameRegistry,
+    /// Segments and attempts of each run, by name.
+    runs: BTreeMap<String, Run>,
+    reached_goals: HashMap<UniqueId, ReachedGoals>,
+    /// Rules that names to look up are normalized by.
+    name_policy: NamePolicy,
+    /// Where the stopwatches are saved. If [`None`], nothing gets saved.
+    state_path: Option<PathBuf>,
+    /// Write started by [`Manager::save_in_background`] that may not be done.
+    saving: Option<JoinHandle<io::Result<()>>>
 }
 
 impl Manager {
@@ -80,7 +158,170 @@ impl Manager {
         Self {
             stopwatches: HashMap::new(),
             access_order: AccessOrder::new(),
-            name_registry: NameRegistry::new()
+            name_registry: NameRegistry::new(),
+            timers: HashMap::new(),
+            timer_access_order: AccessOrder::new(),
+            timer_name_registry: NameRegistry::new(),
+            runs: BTreeMap::new(),
+            reached_goals: HashMap::new(),
+            name_policy: NamePolicy::default(),
+            state_path: None,
+            saving: None
+        }
+    }
+
+    /// Create a [`Manager`] that saves its stopwatches to `state_path`,
+    /// restoring stopwatches that were saved there previously.
+    ///
+    /// If the state file cannot be read, it is moved aside and the manager
+    /// starts off empty.
+    pub fn restore(state_path: PathBuf, policy: &NamePolicy) -> Self {
+        let mut manager = Self::new();
+        match load_state(&state_path) {
+            Ok(Some(state)) => {
+                manager.load(state, policy);
+                info!(
+                    "restored {} stopwatches and {} timers from {:?}",
+                    manager.stopwatches.len(),
+                    manager.timers.len(),
+                    state_path
+                );
+            },
+            Ok(None) => debug!("no state file found at {:?}", state_path),
+            Err(e) => {
+                error!("could not read state file {:?}: {}", state_path, e);
+                match quarantine_state(&state_path) {
+                    Ok(bad_path) => warn!("moved unreadable state file to {:?}", bad_path),
+                    Err(e) => error!("could not move unreadable state file: {}", e)
+                }
+            }
+        }
+        manager.name_policy = *policy;
+        manager.state_path = Some(state_path);
+        manager
+    }
+
+    /// Replace the stopwatches and timers in this manager with those in
+    /// `state`.
+    fn load(&mut self, state: SavedState, policy: &NamePolicy) {
+        let SavedState {
+            stopwatches,
+            access_order,
+            name_registry,
+            timers,
+            timer_access_order,
+            timer_name_registry,
+            runs
+        } = state;
+        self.stopwatches = stopwatches.into_iter()
+            .map(|sw| (sw.identifier.id, sw))
+            .collect();
+        self.name_registry = name_registry;
+        self.access_order = access_order;
+        sanitize_lookups(
+            &mut self.name_registry,
+            &mut self.access_order,
+            &mut self.stopwatches,
+            policy,
+            |sw| &mut sw.identifier
+        );
+        self.timers = timers.into_iter()
+            .map(|t| (t.identifier.id, t))
+            .collect();
+        self.timer_name_registry = timer_name_registry;
+        self.timer_access_order = timer_access_order;
+        sanitize_lookups(
+            &mut self.timer_name_registry,
+            &mut self.timer_access_order,
+            &mut self.timers,
+            policy,
+            |t| &mut t.identifier
+        );
+        self.runs = runs;
+    }
+
+    /// Save stopwatches to the state file, if there is one.
+    pub fn save(&self) -> io::Result<()> {
+        let state_path = match self.state_path {
+            Some(ref p) => p,
+            None => return Ok(())
+        };
+        save_state(state_path, &self.saved_state())?;
+        trace!("saved state to {:?}", state_path);
+        Ok(())
+    }
+
+    /// Like [`Manager::save`], but the file is written by a blocking task so
+    /// that requests can be handled in the meantime. The previous write is
+    /// waited for first so that writes happen in order.
+    pub async fn save_in_background(&mut self) {
+        let state_path = match self.state_path {
+            Some(ref p) => p.clone(),
+            None => return
+        };
+        let bytes = match encode_state(&self.saved_state()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("could not save stopwatches: {}", e);
+                return;
+            }
+        };
+        self.finish_saving().await;
+        self.saving = Some(spawn_blocking(move || {
+            write_state(&state_path, &bytes)?;
+            trace!("saved state to {:?}", state_path);
+            Ok(())
+        }));
+    }
+
+    /// Wait for the write started by [`Manager::save_in_background`], if any.
+    pub async fn finish_saving(&mut self) {
+        let result = match self.saving.take() {
+            Some(saving) => saving.await,
+            None => return
+        };
+        match result {
+            Ok(Ok(())) => {},
+            Ok(Err(e)) => error!("could not save stopwatches: {}", e),
+            Err(e) => error!("could not save stopwatches: {}", e)
+        }
+    }
+
+    fn saved_state(&self) -> SavedStateRef<'_> {
+        SavedStateRef {
+            stopwatches: self.stopwatches.values().map(Stopwatch::snapshot).collect(),
+            access_order: &self.access_order,
+            name_registry: &self.name_registry,
+            timers: self.timers.values().map(Timer::snapshot).collect(),
+            timer_access_order: &self.timer_access_order,
+            timer_name_registry: &self.timer_name_registry,
+            runs: &self.runs
+        }
+    }
+
+    /// Add `attempt` to the history of the run called `name`. Stopwatches
+    /// doing that run are then compared against the new personal best and
+    /// golds.
+    pub fn record_attempt(&mut self, name: &str, attempt: RunAttempt) {
+        match self.runs.get_mut(name) {
+            Some(run) => if run.record(attempt) {
+                info!("new personal best for run {}", name);
+            },
+            None => {
+                debug!("run {} was deleted before the attempt was recorded", name);
+                return;
+            }
+        }
+        self.refresh_run(name);
+    }
+
+    /// Update the [`RunProgress`] of stopwatches doing the run called `name`.
+    fn refresh_run(&mut self, name: &str) {
+        let progress = self.runs.get(name).map(RunProgress::from);
+        for sw in self.stopwatches.values_mut() {
+            if matches!(sw.run, Some(ref run) if run.name == name) {
+                sw.run = progress.clone();
+            }
         }
     }
 
@@ -105,6 +346,119 @@ impl Manager {
         }
     }
 
+    pub fn add_timer(&mut self, timer: Timer) -> Result<(), Identifier> {
+        let id = timer.identifier.id;
+        match self.timers.entry(id) {
+            Entry::Occupied(o) => Err(o.get().identifier.clone()),
+            Entry::Vacant(v) => {
+                match self.timer_name_registry.register(&timer.identifier) {
+                    Ok(()) => {
+                        self.timer_access_order.access_stopwatch(id);
+                        v.insert(timer);
+                        Ok(())
+                    },
+                    Err(oid) => Err(Identifier::new(oid, timer.identifier.name.clone()))
+                }
+            }
+        }
+    }
+
+    pub fn get_mut_timer(&mut self, raw_identifier: &RawIdentifier) -> Option<&mut Timer> {
+        let id = self.get_timer_id(raw_identifier)?;
+        let timer = self.timers.get_mut(&id)?;
+        self.timer_access_order.access_stopwatch(id);
+        Some(timer)
+    }
+
+    pub fn take_timer(&mut self, raw_identifier: &RawIdentifier) -> Option<Timer> {
+        let id = self.get_timer_id(raw_identifier)?;
+        let timer = self.timers.remove(&id)?;
+        self.timer_access_order.delete_stopwatch(id);
+        let _ = self.timer_name_registry.delete(&timer.identifier);
+        Some(timer)
+    }
+
+    fn get_timer_id(&self, raw_identifier: &RawIdentifier) -> Option<UniqueId> {
+        match raw_identifier.clone().to_possible_id_or_name(&self.name_policy) {
+            Ok(id) => Some(id),
+            Err(name) => self.timer_name_registry.get(&name)
+        }
+    }
+
+    /// End timers that have run out. Returns whether any timer expired.
+    pub fn check_timers(&mut self) -> bool {
+        let mut expired = false;
+        for timer in self.timers.values_mut() {
+            if timer.check_expiry() {
+                info!("timer {} expired", timer.identifier);
+                expired = true;
+            }
+        }
+        expired
+    }
+
+    /// Move stopwatches in pomodoro mode or running a program on to their
+    /// next phase or step once the current one has run out, and start new
+    /// laps for stopwatches with an auto-lap policy. Returns whether any
+    /// phase, step or lap ended.
+    pub fn check_phases(&mut self) -> bool {
+        let mut advanced = false;
+        for sw in self.stopwatches.values_mut() {
+            if sw.check_pomodoro() > 0 {
+                if let Some(ref pomodoro) = sw.pomodoro {
+                    info!("stopwatch {} started a {} phase", sw.identifier, pomodoro.phase);
+                }
+                advanced = true;
+            }
+            if sw.check_program() > 0 {
+                if let Some(ref program) = sw.program {
+                    match program.outcome {
+                        Some(outcome) => info!(
+                            "stopwatch {} {} program {}",
+                            sw.identifier,
+                            outcome,
+                            program.program.name
+                        ),
+                        None => info!("stopwatch {} started step {}", sw.identifier, program.step + 1)
+                    }
+                }
+                advanced = true;
+            }
+            if sw.check_auto_lap() > 0 {
+                info!("stopwatch {} started lap {} by itself", sw.identifier, sw.laps());
+                advanced = true;
+            }
+        }
+        advanced
+    }
+
+    /// Announce stopwatches whose total time or current lap has just crossed
+    /// its goal.
+    pub fn check_goals(&mut self) {
+        for (id, sw) in &self.stopwatches {
+            if sw.goals.is_empty() || sw.state() != State::Playing {
+                continue;
+            }
+            let reached = self.reached_goals.entry(*id).or_default();
+            let total = matches!(sw.goals.total, Some(goal) if sw.total_time() >= goal);
+            if total && !reached.total {
+                info!("stopwatch {} reached its goal", sw.identifier);
+            }
+            reached.total = total;
+            let lap_time = sw.last_lap().map(|lap| lap.duration).unwrap_or_default();
+            let lap = match sw.goals.lap {
+                Some(goal) if lap_time >= goal => Some(sw.laps()),
+                _ => None
+            };
+            if lap.is_some() && reached.lap != lap {
+                info!("lap {} of stopwatch {} reached its goal", sw.laps(), sw.identifier);
+            }
+            reached.lap = lap;
+        }
+        let stopwatches = &self.stopwatches;
+        self.reached_goals.retain(|id, _| stopwatches.contains_key(id));
+    }
+
     pub fn get_stopwatch(
         &mut self,
         raw_identifier: &RawIdentifier
@@ -136,13 +490,72 @@ impl Manager {
         Some((sw, match_kind))
     }
 
+    /// Give a [`Stopwatch`] a new name. Nothing is changed if the stopwatch
+    /// cannot be found or if another stopwatch already has that name.
+    pub fn rename_stopwatch(
+        &mut self,
+        raw_identifier: &RawIdentifier,
+        name: Name
+    ) -> Result<&Stopwatch, ServerError> {
+        let id = self.get_id(raw_identifier)
+            .map(|(id, _mk)| id)
+            .filter(|id| self.stopwatches.contains_key(id))
+            .ok_or_else(|| FindStopwatchError {
+                raw_identifier: raw_identifier.get_identifier().to_string(),
+                duplicates: vec![]
+            })?;
+        if let Some(other) = self.name_registry.get(&name).filter(|other| *other != id) {
+            return Err(FindStopwatchError {
+                raw_identifier: name.to_string(),
+                duplicates: vec![Identifier::new(other, name)]
+            }.into());
+        }
+        let sw = self.stopwatches.get_mut(&id).unwrap();
+        let _ = self.name_registry.delete(&sw.identifier);
+        sw.identifier.name = name;
+        self.name_registry.register(&sw.identifier)
+            .expect("new name should not have been registered");
+        self.access_order.access_stopwatch(id);
+        Ok(&self.stopwatches[&id])
+    }
+
     fn get_id(&self, raw_identifier: &RawIdentifier) -> Option<(UniqueId, IdentifierMatch)> {
-        match raw_identifier.clone().to_possible_id_or_name() {
+        match raw_identifier.clone().to_possible_id_or_name(&self.name_policy) {
             Ok(id) => Some((id, IdentifierMatch::Uuid)),
             Err(name) => Some((self.name_registry.get(&name)?, IdentifierMatch::Name))
         }
     }
 
+    /// Replace every tag selector in `raw_identifiers` with the stopwatches
+    /// that have that tag, most recently accessed first. Selectors that match
+    /// nothing are kept as they are so that they can be reported as not found.
+    pub fn expand_tag_selectors(&self, raw_identifiers: &[String]) -> Vec<String> {
+        let mut expanded = vec![];
+        let mut seen = HashSet::new();
+        for raw_str in raw_identifiers {
+            let tag = match Tag::from_selector(raw_str) {
+                Some(tag) => tag,
+                None => {
+                    expanded.push(raw_str.clone());
+                    continue;
+                }
+            };
+            let mut matched = false;
+            for id in self.iter_access_order_id() {
+                if self.stopwatches[id].tags.contains(&tag) {
+                    matched = true;
+                    if seen.insert(*id) {
+                        expanded.push(id.to_string());
+                    }
+                }
+            }
+            if !matched {
+                expanded.push(raw_str.clone());
+            }
+        }
+        expanded
+    }
+
     pub fn get_all_stopwatches_and(&self, state: &mut ActionState<'_>, action: ActionGetStopwatch) {
         for id in self.iter_access_order_id() {
             let sw = self.stopwatches.get(id).unwrap();
@@ -199,8 +612,61 @@ impl Manager {
     }
 }
 
+/// Make sure `registry` and `order` only refer to items in `items`, that
+/// `registry` has the name of every item and that `order` has every item
+/// exactly once.
+///
+/// Items whose names are taken by another item, which can happen when a
+/// snapshot migration changes a name, are given a suffix that follows
+/// `policy`, or left without a name if there is no such suffix.
+fn sanitize_lookups<T>(
+    registry: &mut NameRegistry,
+    order: &mut AccessOrder,
+    items: &mut HashMap<UniqueId, T>,
+    policy: &NamePolicy,
+    identifier: fn(&mut T) -> &mut Identifier
+) {
+    // Older versions registered empty names too
+    registry.registry.retain(|name, id| match items.get_mut(id) {
+        Some(item) => !name.is_empty() && identifier(item).name == *name,
+        None => false
+    });
+    order.order.retain(|id| items.contains_key(id));
+    for id in items.keys() {
+        if !order.contains(id) {
+            order.order.insert(0, *id);
+        }
+    }
+    for id in order.iter() {
+        let identifier = identifier(items.get_mut(id).unwrap());
+        match registry.register(identifier) {
+            Err(other) if other != identifier.id => {
+                let old_name = identifier.name.clone();
+                match registry.free_name(&old_name, policy) {
+                    Ok(name) => {
+                        identifier.name = name;
+                        registry.register(identifier)
+                            .expect("free name should not have been registered");
+                        warn!("{} was renamed from {} because the name was taken",
+                            identifier, old_name);
+                    },
+                    Err(e) => {
+                        identifier.name = Name::default();
+                        warn!("{} lost the name {} because it was taken: {}",
+                            identifier, old_name, e);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
 /// Associates each [`Name`] with a [`UniqueId`].
-#[derive(Clone, Debug)]
+///
+/// Empty names are never registered, so any number of stopwatches can be
+/// left without a name. They can only be found by their [`UniqueId`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NameRegistry {
     pub registry: HashMap<Name, UniqueId>
 }
@@ -211,6 +677,9 @@ impl NameRegistry {
     }
 
     pub fn register(&mut self, identifier: &Identifier) -> Result<(), UniqueId> {
+        if identifier.name.is_empty() {
+            return Ok(());
+        }
         let name = identifier.name.clone();
         let id = identifier.id;
         match self.registry.entry(name) {
@@ -223,12 +692,48 @@ impl NameRegistry {
         self.registry.get(name).map(|id| *id)
     }
 
+    /// Remove the name of `identifier`. Returns the [`UniqueId`] of the
+    /// stopwatch that has the name instead if it belongs to another
+    /// stopwatch, in which case nothing is removed.
     pub fn delete(&mut self, identifier: &Identifier) -> Result<usize, UniqueId> {
-        match self.registry.remove(&identifier.name) {
-            Some(id) if id != identifier.id => Err(id),
-            Some(_) => Ok(1),
-            None => Ok(0)
+        match self.registry.entry(identifier.name.clone()) {
+            Entry::Occupied(o) if *o.get() != identifier.id => Err(*o.get()),
+            Entry::Occupied(o) => { o.remove(); Ok(1) },
+            Entry::Vacant(_) => Ok(0)
+        }
+    }
+
+    /// Name for a stopwatch started without one, following `template`.
+    /// `{date}` is replaced by today's date and `{n}` by the smallest number
+    /// from 1 that gives a name no other stopwatch has. Long templates are
+    /// cut short by `policy` before the number.
+    ///
+    /// Gives up if none of the first `len + 1` numbers do, which only happens
+    /// if the names get too long for `policy`.
+    pub fn generate_name(&self, template: &str, policy: &NamePolicy) -> Result<Name, ServerError> {
+        let dated = template.replace("{date}", &Local::now().format("%Y-%m-%d").to_string());
+        for n in 1..=self.registry.len() + 1 {
+            let name = policy.numbered(&dated, n).map_err(ServerError::BadName)?;
+            if self.get(&name).is_none() {
+                return Ok(name);
+            }
+        }
+        Err(ServerError::Other(format!("no free name for template {}", template)))
+    }
+
+    /// `name` with the first suffix from `-2` that makes it a name no other
+    /// stopwatch has.
+    ///
+    /// Gives up if none of the first `len + 1` suffixes do, which only happens
+    /// if the names get too long for `policy`.
+    pub fn free_name(&self, name: &Name, policy: &NamePolicy) -> Result<Name, ServerError> {
+        for n in 2..=self.registry.len() + 2 {
+            let name = policy.with_suffix(name, n).map_err(ServerError::BadName)?;
+            if self.get(&name).is_none() {
+                return Ok(name);
+            }
         }
+        Err(ServerError::Other(format!("no free name like {}", name)))
     }
 }
 
@@ -239,6 +744,7 @@ impl Default for NameRegistry {
 }
 
 /// Order in which [`Stopwatch`]es were last accessed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AccessOrder {
     pub order: Vec<UniqueId>
 }
@@ -267,6 +773,12 @@ impl AccessOrder {
     }
 }
 
+impl Default for AccessOrder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ops::Deref for AccessOrder {
     type Target = Vec<UniqueId>;
 
@@ -275,43 +787,138 @@ impl ops::Deref for AccessOrder {
     }
 }
 
-/// Manager function to start a [`Stopwatch`].
-async fn start(manager: &mut Manager, req: &Request) -> Reply {
+/// Manager function to start a [`Stopwatch`] for each given name, or one
+/// stopwatch without a name if none are given. Stopwatches without a name are
+/// named after [`ManagerConfig::name_template`] if there is one.
+async fn start(manager: &mut Manager, req: &Request, config: &ManagerConfig) -> Reply {
     // Create a new reply. Can populate with success or error later.
     let mut reply = Reply::new(StartAnswer.into());
 
     // Retrieve arguments.
     let start_args = match req.specific_args {
         SpecificArgs::Start(ref sa) => sa,
-        _ => panic!("fuck")
+        _ => panic!("start called without SpecificArgs::Start")
     };
-    let given_name = req.common_args.raw_identifiers.first().cloned()
-        .unwrap_or_else(|| String::new());
-    
-    // Calculate name of the new stopwatch.
-    let name: Option<Name> = if start_args.fix_bad_names {
-        Some(Name::fixed(given_name.clone()))
-    } else {
-        match Name::new(given_name.clone()) {
-            Ok(n) => Some(n),
-            Err(e) => {
-                reply.extend_uncollected_errors(
-                    [(Some(given_name.clone()), ServerError::BadName(e))]
-                );
-                None
+    let mut given_names = req.common_args.raw_identifiers.clone();
+    if given_names.is_empty() {
+        given_names.push(String::new());
+    }
+
+    // Make every stopwatch before adding any, so nothing is added if one of
+    // them is bad and the batch is all or nothing
+    let mut stopwatches = Vec::with_capacity(given_names.len());
+    let mut failed = false;
+    for given_name in given_names {
+        match new_stopwatch(manager, start_args, &config.name_policy, &given_name) {
+            Ok(stopwatch) => stopwatches.push((given_name, stopwatch)),
+            Err(error) => {
+                reply.extend_uncollected_errors([(Some(given_name), error)]);
+                failed = true;
             }
         }
-    };
+    }
 
-    // Create new stopwatch.
-    if let Some(name) = name {
-        // Start stopwatch first, delete if need be
-        let stopwatch = Stopwatch::start(name.clone());
-        let details = StopwatchDetails::from_stopwatch(&stopwatch, req.common_args.verbose);
+    // Work out what happens to each stopwatch. `names` also has the names
+    // given out earlier in this batch, so the whole batch is checked before
+    // anything is added or replaced
+    let mut names = manager.name_registry.clone();
+    let mut planned = Vec::with_capacity(stopwatches.len());
+    for (given_name, mut stopwatch) in stopwatches {
+        let name = &stopwatch.identifier.name;
+        let named = if let (true, Some(template)) = (name.is_empty(), &config.name_template) {
+            names.generate_name(template, &config.name_policy)
+        } else if renamed_by_fix(start_args.fix_bad_names, name, &given_name)
+            && names.get(name).is_some()
+        {
+            names.free_name(name, &config.name_policy)
+        } else {
+            Ok(name.clone())
+        };
+        let plan = named.and_then(|name| {
+            let taken = |other| FindStopwatchError {
+                raw_identifier: given_name.clone(),
+                duplicates: vec![Identifier::new(other, name.clone())]
+            };
+            stopwatch.identifier.name = name.clone();
+            match (names.get(&name), start_args.if_exists) {
+                (None, _) => Ok(StartPlan::Add(stopwatch)),
+                (Some(_), IfExists::Suffix) => {
+                    stopwatch.identifier.name = names.free_name(&name, &config.name_policy)?;
+                    Ok(StartPlan::Add(stopwatch))
+                },
+                // Given earlier in this batch, so there is nothing to reuse or
+                // replace yet
+                (Some(other), _) if manager.name_registry.get(&name) != Some(other) => {
+                    Err(taken(other).into())
+                },
+                (Some(other), IfExists::Error) => Err(taken(other).into()),
+                (Some(other), IfExists::Reuse) => Ok(StartPlan::Existing(other, false)),
+                (Some(other), IfExists::Resume)
+                    if !config.allow_reopen && manager.stopwatches[&other].state().ended() =>
+                {
+                    Err(reopen_disabled())
+                },
+                (Some(other), IfExists::Resume) => Ok(StartPlan::Existing(other, true)),
+                (Some(other), IfExists::Restart) => Ok(StartPlan::Replace(other, stopwatch))
+            }
+        });
+        match plan {
+            Ok(plan) => {
+                if let StartPlan::Add(ref sw) | StartPlan::Replace(_, ref sw) = plan {
+                    names.registry.remove(&sw.identifier.name);
+                    let _ = names.register(&sw.identifier);
+                }
+                planned.push((given_name, plan));
+            },
+            Err(error) => {
+                reply.extend_uncollected_errors([(Some(given_name), error)]);
+                failed = true;
+            }
+        }
+    }
+    if failed && start_args.all_or_nothing {
+        let message = "not started because another stopwatch could not be".to_string();
+        reply.extend_uncollected_errors(planned.into_iter()
+            .map(|(given_name, _)| (Some(given_name), ServerError::Other(message.clone()))));
+        return reply;
+    }
 
+    // Replies are keyed by the name each stopwatch ends up with, or its id
+    // if it has no name
+    for (given_name, plan) in planned {
+        let stopwatch = match plan {
+            StartPlan::Add(stopwatch) => stopwatch,
+            StartPlan::Existing(id, resume) => {
+                let existing = RawIdentifier::new(id.to_string());
+                let (sw, _) = manager.get_mut_stopwatch(&existing).unwrap();
+                if resume {
+                    match sw.state() {
+                        State::Playing => {},
+                        State::Paused => { sw.play(); },
+                        State::Ended => { sw.reopen(true); }
+                    }
+                }
+                let details = StopwatchDetails::from_stopwatch(sw, req.common_args.verbose);
+                reply.extend_successful([(sw.identifier.to_string(), details)]);
+                continue;
+            },
+            StartPlan::Replace(id, stopwatch) => {
+                let existing = RawIdentifier::new(id.to_string());
+                let (mut old, _) = manager.take_stopwatch(&existing).unwrap();
+                if !old.end().ended() {
+                    if let (Some(run), Some(attempt)) = (&old.run, old.run_attempt()) {
+                        manager.record_attempt(&run.name, attempt);
+                    }
+                }
+                info!("stopwatch {} was replaced by {}", old.identifier, stopwatch.identifier.id);
+                stopwatch
+            }
+        };
+        let key = stopwatch.identifier.to_string();
+        let details = StopwatchDetails::from_stopwatch(&stopwatch, req.common_args.verbose);
         match manager.add_stopwatch(stopwatch) {
             Ok(()) => {
-                reply.extend_successful([(Into::<String>::into(name), details)]);
+                reply.extend_successful([(key, details)]);
             },
             Err(identifier) => {
                 trace!("stopwatch with the same name or uuid already exists");
@@ -327,12 +934,365 @@ async fn start(manager: &mut Manager, req: &Request) -> Reply {
     reply
 }
 
+/// What [`start`] does for one of the names it was given.
+enum StartPlan {
+    /// Add a new stopwatch.
+    Add(Stopwatch),
+    /// Reply with the existing stopwatch, playing it first if the flag is set.
+    Existing(UniqueId, bool),
+    /// End and delete the existing stopwatch, then add a new one in its place.
+    Replace(UniqueId, Stopwatch)
+}
+
+/// Make the [`Stopwatch`] that [`start`] adds for `given_name`.
+fn new_stopwatch(
+    manager: &Manager,
+    start_args: &StartArgs,
+    policy: &NamePolicy,
+    given_name: &str
+) -> Result<Stopwatch, ServerError> {
+    // Calculate name of the new stopwatch.
+    let name = if start_args.fix_bad_names {
+        policy.fix(given_name)
+    } else {
+        policy.check(given_name).map_err(ServerError::BadName)?
+    };
+
+    let mut stopwatch = match start_args.at {
+        Some(at) => Stopwatch::start_at(name, at).map_err(|error| {
+            ServerError::InvalidLap(InvalidLap { raw_identifier: given_name.to_string(), error })
+        })?,
+        None if start_args.paused => Stopwatch::new(name),
+        None => Stopwatch::start(name)
+    };
+    stopwatch.tags.extend(start_args.tags.iter().cloned());
+    stopwatch.goals = start_args.goals;
+    if let Some(config) = start_args.pomodoro {
+        stopwatch.start_pomodoro(config);
+    }
+    if let Some(ref program) = start_args.program {
+        stopwatch.start_program(program.clone());
+    }
+    stopwatch.auto_lap = start_args.auto_lap;
+    if let Some(ref run_name) = start_args.run {
+        match manager.runs.get(&*policy.lookup(run_name)) {
+            Some(run) => stopwatch.run = Some(RunProgress::from(run)),
+            None => return Err(ServerError::Other(format!("could not find run {}", run_name)))
+        }
+    }
+    Ok(stopwatch)
+}
+
+/// Error for reopening a stopwatch when swd.toml doesn't allow it.
+fn reopen_disabled() -> ServerError {
+    ServerError::Other("reopening stopwatches is disabled by swd.toml".to_string())
+}
+
+/// Whether `--fix-bad-names` changed `given_name` into `name`. Such names
+/// are given a suffix instead of clashing with an existing stopwatch.
+fn renamed_by_fix(fix_bad_names: bool, name: &Name, given_name: &str) -> bool {
+    fix_bad_names && **name != *given_name
+}
+
+/// Manager function to create [`Stopwatch`]es from laps that were timed
+/// somewhere else.
+fn import(manager: &mut Manager, req: &Request, policy: &NamePolicy) -> Reply {
+    let mut reply = Reply::new(ImportAnswer.into());
+
+    let ImportArgs { fix_bad_names, tags, stopwatches } = match req.specific_args {
+        SpecificArgs::Import(ref ia) => ia,
+        _ => panic!("import called without SpecificArgs::Import")
+    };
+    let raw_ids = &req.common_args.raw_identifiers;
+    if raw_ids.len() != stopwatches.len() {
+        reply.extend_uncollected_errors([(
+            None,
+            ServerError::Other("each imported stopwatch needs exactly one name".to_string())
+        )]);
+        return reply;
+    }
+
+    for (given_name, imported) in raw_ids.iter().zip(stopwatches) {
+        let name = if *fix_bad_names {
+            let name = policy.fix(given_name);
+            match manager.name_registry.get(&name) {
+                Some(_) if renamed_by_fix(true, &name, given_name) => {
+                    manager.name_registry.free_name(&name, policy)
+                },
+                _ => Ok(name)
+            }
+        } else {
+            policy.check(given_name).map_err(ServerError::BadName)
+        };
+        let name = match name {
+            Ok(n) => n,
+            Err(e) => {
+                reply.extend_uncollected_errors([(Some(given_name.clone()), e)]);
+                continue;
+            }
+        };
+        let mut stopwatch = match Stopwatch::from_laps(name, imported.start, &imported.laps) {
+            Ok(sw) => sw,
+            Err(error) => {
+                invalid_lap(&mut reply, given_name.clone(), error);
+                continue;
+            }
+        };
+        stopwatch.tags.extend(tags.iter().cloned());
+        let details = StopwatchDetails::from_stopwatch(&stopwatch, req.common_args.verbose);
+        match manager.add_stopwatch(stopwatch) {
+            Ok(()) => reply.extend_successful([(given_name.clone(), details)]),
+            Err(identifier) => {
+                let error = FindStopwatchError {
+                    raw_identifier: given_name.clone(),
+                    duplicates: vec![identifier]
+                };
+                reply.extend_uncollected_errors([(Some(given_name.clone()), error.into())]);
+            }
+        }
+    }
+
+    reply
+}
+
+/// Manager function to rename a [`Stopwatch`].
+fn rename(manager: &mut Manager, req: &Request, policy: &NamePolicy) -> Reply {
+    let mut reply = Reply::new(RenameAnswer.into());
+
+    let RenameArgs { name, fix_bad_names } = match req.specific_args {
+        SpecificArgs::Rename(ref ra) => ra,
+        _ => panic!("rename called without SpecificArgs::Rename")
+    };
+    let raw_id = req.common_args.raw_identifiers.first().cloned().unwrap_or_default();
+
+    let name = if *fix_bad_names {
+        let fixed = policy.fix(name);
+        let target = manager.get_id(&RawIdentifier::new(raw_id.clone())).map(|(id, _)| id);
+        match manager.name_registry.get(&fixed) {
+            Some(other) if Some(other) != target && renamed_by_fix(true, &fixed, name) => {
+                manager.name_registry.free_name(&fixed, policy)
+            },
+            _ => Ok(fixed)
+        }
+    } else {
+        policy.check(name).map_err(ServerError::BadName)
+    };
+    let name = match name {
+        Ok(n) => n,
+        Err(e) => {
+            reply.extend_uncollected_errors([(Some(raw_id), e)]);
+            return reply;
+        }
+    };
+
+    match manager.rename_stopwatch(&RawIdentifier::new(raw_id.clone()), name) {
+        Ok(sw) => reply.extend_successful([(
+            raw_id,
+            StopwatchDetails::from_stopwatch(sw, req.common_args.verbose)
+        )]),
+        Err(e) => reply.extend_uncollected_errors([(Some(raw_id), e)])
+    }
+    reply
+}
+
+/// Manager function for [`Timer`]s.
+fn timer(manager: &mut Manager, req: &Request, policy: &NamePolicy) -> Reply {
+    let mut reply = Reply::new(TimerAnswer::default().into());
+    let action = match req.specific_args {
+        SpecificArgs::Timer(ref ta) => &ta.action,
+        _ => panic!("timer called without SpecificArgs::Timer")
+    };
+    let raw_ids = &req.common_args.raw_identifiers;
+    let mut timers = Vec::with_capacity(raw_ids.len());
+
+    match action {
+        TimerAction::Start { target, fix_bad_names } => {
+            // Without any names, a single timer without a name is started
+            let unnamed = [String::new()];
+            let given_names = if raw_ids.is_empty() { &unnamed[..] } else { &raw_ids[..] };
+            for given_name in given_names {
+                let name = if *fix_bad_names {
+                    let name = policy.fix(given_name);
+                    match manager.timer_name_registry.get(&name) {
+                        Some(_) if renamed_by_fix(true, &name, given_name) => {
+                            manager.timer_name_registry.free_name(&name, policy)
+                        },
+                        _ => Ok(name)
+                    }
+                } else {
+                    policy.check(given_name).map_err(ServerError::BadName)
+                };
+                let name = match name {
+                    Ok(n) => n,
+                    Err(e) => {
+                        reply.extend_uncollected_errors([(Some(given_name.clone()), e)]);
+                        continue;
+                    }
+                };
+                let timer = Timer::start(name, *target);
+                let details = TimerDetails::from_timer(&timer);
+                match manager.add_timer(timer) {
+                    Ok(()) => timers.push((given_name.clone(), details)),
+                    Err(identifier) => {
+                        let error = FindTimerError {
+                            raw_identifier: given_name.clone(),
+                            duplicates: vec![identifier]
+                        };
+                        reply.extend_uncollected_errors([(Some(given_name.clone()), error.into())]);
+                    }
+                }
+            }
+        },
+        TimerAction::Info if raw_ids.is_empty() => {
+            for id in manager.timer_access_order.iter() {
+                let timer = &manager.timers[id];
+                timers.push((timer.identifier.to_string(), TimerDetails::from_timer(timer)));
+            }
+        },
+        TimerAction::Delete => {
+            for raw_id in raw_ids {
+                match manager.take_timer(&RawIdentifier::new(raw_id.clone())) {
+                    Some(timer) => timers.push((raw_id.clone(), TimerDetails::from_timer(&timer))),
+                    None => timer_not_found(&mut reply, raw_id.clone())
+                }
+            }
+        },
+        TimerAction::Info | TimerAction::Play | TimerAction::Pause | TimerAction::Cancel => {
+            for raw_id in raw_ids {
+                let timer = match manager.get_mut_timer(&RawIdentifier::new(raw_id.clone())) {
+                    Some(timer) => timer,
+                    None => {
+                        timer_not_found(&mut reply, raw_id.clone());
+                        continue;
+                    }
+                };
+                let (state, error_condition) = match action {
+                    TimerAction::Play => {
+                        let state = timer.play();
+                        (state, matches!(state, State::Playing | State::Ended))
+                    },
+                    TimerAction::Pause => {
+                        let state = timer.pause();
+                        (state, matches!(state, State::Paused | State::Ended))
+                    },
+                    TimerAction::Cancel => {
+                        let state = timer.cancel();
+                        (state, state.ended())
+                    },
+                    _ => (timer.state(), false)
+                };
+                if error_condition {
+                    reply.extend_uncollected_errors([(
+                        Some(raw_id.clone()),
+                        InvalidState { raw_identifier: raw_id.clone(), state }.into()
+                    )]);
+                } else {
+                    timers.push((raw_id.clone(), TimerDetails::from_timer(timer)));
+                }
+            }
+        }
+    }
+
+    reply.specific_answer = TimerAnswer { timers }.into();
+    reply
+}
+
+/// Manager function for [`Run`]s.
+fn run(manager: &mut Manager, req: &Request, policy: &NamePolicy) -> Reply {
+    let mut reply = Reply::new(RunAnswer::default().into());
+    let action = match req.specific_args {
+        SpecificArgs::Run(ref ra) => &ra.action,
+        _ => panic!("run called without SpecificArgs::Run")
+    };
+    let raw_ids = &req.common_args.raw_identifiers;
+    let mut runs = Vec::with_capacity(raw_ids.len());
+
+    match action {
+        RunAction::Define { segments, replace } => {
+            for given_name in raw_ids {
+                match define_run(manager, policy, given_name, segments, *replace) {
+                    Ok(run) => runs.push(run),
+                    Err(e) => reply.extend_uncollected_errors([(Some(given_name.clone()), e)])
+                }
+            }
+        },
+        RunAction::Info if raw_ids.is_empty() => runs.extend(manager.runs.values().cloned()),
+        RunAction::Info => {
+            for name in raw_ids {
+                match manager.runs.get(&*policy.lookup(name)) {
+                    Some(run) => runs.push(run.clone()),
+                    None => run_not_found(&mut reply, name.clone())
+                }
+            }
+        },
+        RunAction::Delete => {
+            for name in raw_ids {
+                match manager.runs.remove(&*policy.lookup(name)) {
+                    Some(run) => runs.push(run),
+                    None => run_not_found(&mut reply, name.clone())
+                }
+            }
+        }
+    }
+
+    reply.specific_answer = RunAnswer { runs }.into();
+    reply
+}
+
+/// Create a run called `given_name`, or replace the existing run if
+/// `replace` is set. A replaced run keeps its history if its segments stay
+/// the same.
+fn define_run(
+    manager: &mut Manager,
+    policy: &NamePolicy,
+    given_name: &str,
+    segments: &[String],
+    replace: bool
+) -> Result<Run, ServerError> {
+    let name: String = policy.check(given_name).map_err(ServerError::BadName)?.into();
+    if name.is_empty() || segments.is_empty() {
+        return Err(ServerError::Other("a run needs a name and at least one segment".to_string()));
+    }
+    let run = match manager.runs.get(&name) {
+        Some(_) if !replace => return Err(ServerError::Other(format!(
+            "run {} already exists, pass --replace to define it again",
+            name
+        ))),
+        // The attempts still fit the segments
+        Some(existing) if existing.has_segments(segments) => existing.clone(),
+        _ => Run::new(name.clone(), segments.iter().cloned())
+    };
+    manager.runs.insert(name.clone(), run.clone());
+    manager.refresh_run(&name);
+    Ok(run)
+}
+
+fn run_not_found(reply: &mut Reply, name: String) {
+    let message = format!("could not find run {}", name);
+    reply.extend_uncollected_errors([(Some(name), ServerError::Other(message))]);
+}
+
+/// Take the attempt of a stopwatch doing a run, if it is doing one.
+fn take_attempt(attempts: &mut Vec<(String, RunAttempt)>, sw: &Stopwatch) {
+    if let (Some(run), Some(attempt)) = (&sw.run, sw.run_attempt()) {
+        attempts.push((run.name.clone(), attempt));
+    }
+}
+
+fn timer_not_found(reply: &mut Reply, raw_identifier: String) {
+    reply.add_errors([FindTimerError { raw_identifier, duplicates: vec![] }.into()]);
+}
+
 fn not_found(reply: &mut Reply, raw_identifier: String) {
     reply.add_errors([FindStopwatchError { raw_identifier, duplicates: vec![] }.into()]);
 }
 
+fn invalid_lap(reply: &mut Reply, raw_identifier: String, error: LapError) {
+    reply.add_errors([InvalidLap { raw_identifier, error }.into()]);
+}
+
 fn info_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, .. } = state;
     match sw {
         Some(sw) => {
             let verbose = request.common_args.verbose;
@@ -382,11 +1342,22 @@ fn atrmis(
 }
 
 fn stop_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, attempts } = state;
+    let at = match request.specific_args {
+        SpecificArgs::Stop(StopArgs { at }) => at,
+        _ => panic!("stop_action called without SpecificArgs::Stop")
+    };
     match sw {
         Some(sw) => {
             let verbose = request.common_args.verbose;
-            let state = sw.end();
+            let state = match at.map(|at| sw.end_at(at)) {
+                Some(Ok(state)) => state,
+                Some(Err(error)) => return invalid_lap(reply, raw_id, error),
+                None => sw.end()
+            };
+            if !state.ended() {
+                take_attempt(attempts, sw);
+            }
             atrmis(reply, raw_id, sw, verbose, state, state == State::Ended);
         },
         None => not_found(reply, raw_id)
@@ -394,11 +1365,19 @@ fn stop_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mu
 }
 
 fn play_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, .. } = state;
+    let at = match request.specific_args {
+        SpecificArgs::Play(PlayArgs { at }) => at,
+        _ => panic!("play_action called without SpecificArgs::Play")
+    };
     match sw {
         Some(sw) => {
             let v = request.common_args.verbose;
-            let state = sw.play();
+            let state = match at.map(|at| sw.play_at(at)) {
+                Some(Ok(state)) => state,
+                Some(Err(error)) => return invalid_lap(reply, raw_id, error),
+                None => sw.play()
+            };
             atrmis(reply, raw_id, sw, v, state, matches!(state, State::Playing | State::Ended));
         },
         None => not_found(reply, raw_id)
@@ -406,11 +1385,19 @@ fn play_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mu
 }
 
 fn pause_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, .. } = state;
+    let at = match request.specific_args {
+        SpecificArgs::Pause(PauseArgs { at }) => at,
+        _ => panic!("pause_action called without SpecificArgs::Pause")
+    };
     match sw {
         Some(sw) => {
             let v = request.common_args.verbose;
-            let state = sw.pause();
+            let state = match at.map(|at| sw.pause_at(at)) {
+                Some(Ok(state)) => state,
+                Some(Err(error)) => return invalid_lap(reply, raw_id, error),
+                None => sw.pause()
+            };
             atrmis(reply, raw_id, sw, v, state, matches!(state, State::Paused | State::Ended));
         },
         None => not_found(reply, raw_id)
@@ -418,19 +1405,252 @@ fn pause_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&m
 }
 
 fn lap_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, attempts } = state;
+    let LapArgs { annotation, at, paused } = match request.specific_args {
+        SpecificArgs::Lap(ref la) => la,
+        _ => panic!("lap_action called without SpecificArgs::Lap")
+    };
     match sw {
         Some(sw) => {
             let v = request.common_args.verbose;
-            let state = sw.new_lap(true);
+            let laps_before = sw.laps();
+            let state = match at.map(|at| sw.new_lap_at(at)) {
+                Some(Ok(state)) => state,
+                Some(Err(error)) => return invalid_lap(reply, raw_id, error),
+                None => sw.new_lap(!paused)
+            };
+            // Label the lap that just ended
+            if !state.ended() && !annotation.is_empty() {
+                let _ = sw.annotate_lap(laps_before - 1, annotation);
+            }
+            // The last segment of a run was just finished
+            if !state.ended() && sw.state().ended() {
+                take_attempt(attempts, sw);
+            }
             atrmis(reply, raw_id, sw, v, state, state.ended());
         },
         None => not_found(reply, raw_id)
     }
 }
 
+fn adjust_lap_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let AdjustLapArgs { lap, adjustment } = match request.specific_args {
+        SpecificArgs::AdjustLap(ref ala) => ala,
+        _ => panic!("adjust_lap_action called without SpecificArgs::AdjustLap")
+    };
+    match sw {
+        Some(sw) => {
+            let result = sw.find_lap(*lap).and_then(|index| sw.adjust_lap(index, *adjustment));
+            match result {
+                Ok(()) => reply.extend_successful([(
+                    raw_id,
+                    StopwatchDetails::from_stopwatch(sw, request.common_args.verbose)
+                )]),
+                Err(error) => invalid_lap(reply, raw_id, error)
+            }
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn add_lap_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let AddLapArgs { start, duration, annotation } = match request.specific_args {
+        SpecificArgs::AddLap(ref ala) => ala,
+        _ => panic!("add_lap_action called without SpecificArgs::AddLap")
+    };
+    match sw {
+        Some(sw) => match sw.insert_lap(*start, *duration, annotation) {
+            Ok(_) => reply.extend_successful([(
+                raw_id,
+                StopwatchDetails::from_stopwatch(sw, request.common_args.verbose)
+            )]),
+            Err(error) => invalid_lap(reply, raw_id, error)
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn modify_lap_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let ModifyLapArgs { lap, action } = match request.specific_args {
+        SpecificArgs::ModifyLap(ref mla) => mla,
+        _ => panic!("modify_lap_action called without SpecificArgs::ModifyLap")
+    };
+    match sw {
+        Some(sw) => {
+            let result = sw.find_lap(*lap).and_then(|index| match action {
+                LapAction::Delete => sw.delete_lap(index).map(|_| ()),
+                LapAction::Merge => sw.merge_laps(index),
+                LapAction::Split { offset } => sw.split_lap(index, *offset),
+                LapAction::SetDuration { duration } => sw.set_lap_duration(index, *duration)
+            });
+            match result {
+                Ok(()) => reply.extend_successful([(
+                    raw_id,
+                    StopwatchDetails::from_stopwatch(sw, request.common_args.verbose)
+                )]),
+                Err(error) => invalid_lap(reply, raw_id, error)
+            }
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn edit_lap_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let EditLapArgs { lap, annotation } = match request.specific_args {
+        SpecificArgs::EditLap(ref ela) => ela,
+        _ => panic!("edit_lap_action called without SpecificArgs::EditLap")
+    };
+    match sw {
+        Some(sw) => {
+            let result = sw.find_lap(*lap).and_then(|index| sw.annotate_lap(index, annotation));
+            match result {
+                Ok(()) => reply.extend_successful([(
+                    raw_id,
+                    StopwatchDetails::from_stopwatch(sw, request.common_args.verbose)
+                )]),
+                Err(error) => invalid_lap(reply, raw_id, error)
+            }
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn tag_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let TagArgs { add, remove } = match request.specific_args {
+        SpecificArgs::Tag(ref ta) => ta,
+        _ => panic!("tag_action called without SpecificArgs::Tag")
+    };
+    match sw {
+        Some(sw) => {
+            for tag in remove {
+                sw.tags.remove(tag);
+            }
+            sw.tags.extend(add.iter().cloned());
+            let v = request.common_args.verbose;
+            atrmis(reply, raw_id, sw, v, sw.state(), false);
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn goal_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let GoalArgs { goals } = match request.specific_args {
+        SpecificArgs::Goal(ref ga) => ga,
+        _ => panic!("goal_action called without SpecificArgs::Goal")
+    };
+    match sw {
+        Some(sw) => {
+            sw.goals = *goals;
+            let v = request.common_args.verbose;
+            atrmis(reply, raw_id, sw, v, sw.state(), false);
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn pomodoro_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let PomodoroArgs { action } = match request.specific_args {
+        SpecificArgs::Pomodoro(ref pa) => pa,
+        _ => panic!("pomodoro_action called without SpecificArgs::Pomodoro")
+    };
+    let sw = match sw {
+        Some(sw) if sw.pomodoro.is_some() => sw,
+        Some(_) => {
+            let message = format!("{} is not in pomodoro mode", raw_id);
+            return reply.extend_uncollected_errors([(Some(raw_id), ServerError::Other(message))]);
+        },
+        None => return not_found(reply, raw_id)
+    };
+    let v = request.common_args.verbose;
+    let state = sw.state();
+    match action {
+        PomodoroAction::Skip => {
+            sw.next_phase();
+        },
+        PomodoroAction::Stop => {
+            sw.end();
+        }
+    }
+    atrmis(reply, raw_id, sw, v, state, state == State::Ended);
+}
+
+fn program_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let ProgramArgs { action } = match request.specific_args {
+        SpecificArgs::Program(ref pa) => pa,
+        _ => panic!("program_action called without SpecificArgs::Program")
+    };
+    let sw = match sw {
+        Some(sw) => sw,
+        None => return not_found(reply, raw_id)
+    };
+    let problem = match sw.program {
+        None => Some("is not running a program"),
+        Some(ref program) if program.outcome.is_some() => Some("has already finished its program"),
+        Some(_) => None
+    };
+    if let Some(problem) = problem {
+        let message = format!("{} {}", raw_id, problem);
+        return reply.extend_uncollected_errors([(Some(raw_id), ServerError::Other(message))]);
+    }
+    let v = request.common_args.verbose;
+    let state = sw.state();
+    match action {
+        ProgramAction::Skip => {
+            sw.next_step();
+        },
+        ProgramAction::Abort => {
+            sw.end();
+        }
+    }
+    atrmis(reply, raw_id, sw, v, state, state == State::Ended);
+}
+
+fn reset_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, attempts } = state;
+    let ResetArgs { archive, paused } = match request.specific_args {
+        SpecificArgs::Reset(ref ra) => ra,
+        _ => panic!("reset_action called without SpecificArgs::Reset")
+    };
+    match sw {
+        Some(sw) => {
+            let v = request.common_args.verbose;
+            // Resetting in the middle of a run gives up on the attempt
+            if !sw.state().ended() {
+                take_attempt(attempts, sw);
+            }
+            let state = sw.reset(*archive, !paused);
+            atrmis(reply, raw_id, sw, v, state, false);
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
+fn reopen_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<&mut Stopwatch>) {
+    let ActionState { reply, request, .. } = state;
+    let ReopenArgs { paused } = match request.specific_args {
+        SpecificArgs::Reopen(ref ra) => ra,
+        _ => panic!("reopen_action called without SpecificArgs::Reopen")
+    };
+    match sw {
+        Some(sw) => {
+            let v = request.common_args.verbose;
+            let state = sw.reopen(!paused);
+            atrmis(reply, raw_id, sw, v, state, !state.ended());
+        },
+        None => not_found(reply, raw_id)
+    }
+}
+
 fn delete_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<Stopwatch>) {
-    let ActionState { reply, request } = state;
+    let ActionState { reply, request, .. } = state;
     match sw {
         Some(sw) => {
             let v = request.common_args.verbose;
@@ -441,14 +1661,45 @@ fn delete_action<'rq>(state: &mut ActionState<'rq>, raw_id: String, sw: Option<S
 }
 
 /// Run a [`Manager`].
-pub async fn manage(mut manager: Manager, mut req_rx: JobReceiver) {
+pub async fn manage(mut manager: Manager, mut req_rx: JobReceiver, config_rx: ConfigReceiver) {
     debug!("start manage");
-    while let Some(message) = req_rx.recv().await {
+    let mut ticker = interval(TICK_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // Changes are saved on the next tick, so a burst of requests is only
+    // saved once
+    let mut unsaved = false;
+    loop {
+        let message = tokio::select! {
+            message = req_rx.recv() => match message {
+                Some(message) => message,
+                None => break
+            },
+            _ = ticker.tick() => {
+                // Timers have to expire even if no one is asking about them
+                let advanced = manager.check_phases();
+                manager.check_goals();
+                if manager.check_timers() || advanced || unsaved {
+                    manager.save_in_background().await;
+                    unsaved = false;
+                }
+                continue;
+            }
+        };
         trace!("manage received message");
+        // Make sure replies don't show timers that should have expired
+        let expired = manager.check_timers();
+        let advanced = manager.check_phases();
+        manager.check_goals();
         let req = message.action;
+        unsaved = unsaved || expired || advanced || changes_state(&req);
         let identifiers = &req.common_args.raw_identifiers;
+        let name_policy = config_rx.borrow().name_policy;
+        manager.name_policy = name_policy;
         let reply = match req.specific_args {
-            SpecificArgs::Start(_) => start(&mut manager, &req).await,
+            SpecificArgs::Start(_) => {
+                let config = config_rx.borrow().clone();
+                start(&mut manager, &req, &config).await
+            },
             SpecificArgs::Info(_) => if identifiers.len() == 0 {
                 Reply::new(InfoAnswer::All(InfoAll::default()).into())
             } else {
@@ -458,10 +1709,24 @@ pub async fn manage(mut manager: Manager, mut req_rx: JobReceiver) {
             SpecificArgs::Play(_) => Reply::new(PlayAnswer.into()),
             SpecificArgs::Pause(_) => Reply::new(PauseAnswer.into()),
             SpecificArgs::Lap(_) => Reply::new(LapAnswer.into()),
-            SpecificArgs::Delete(_) => Reply::new(DeleteAnswer.into())
+            SpecificArgs::Delete(_) => Reply::new(DeleteAnswer.into()),
+            SpecificArgs::Timer(_) => timer(&mut manager, &req, &name_policy),
+            SpecificArgs::EditLap(_) => Reply::new(EditLapAnswer.into()),
+            SpecificArgs::Tag(_) => Reply::new(TagAnswer.into()),
+            SpecificArgs::Rename(_) => rename(&mut manager, &req, &name_policy),
+            SpecificArgs::Reset(_) => Reply::new(ResetAnswer.into()),
+            SpecificArgs::Reopen(_) => Reply::new(ReopenAnswer.into()),
+            SpecificArgs::AdjustLap(_) => Reply::new(AdjustLapAnswer.into()),
+            SpecificArgs::AddLap(_) => Reply::new(AddLapAnswer.into()),
+            SpecificArgs::ModifyLap(_) => Reply::new(ModifyLapAnswer.into()),
+            SpecificArgs::Run(_) => run(&mut manager, &req, &name_policy),
+            SpecificArgs::Import(_) => import(&mut manager, &req, &name_policy),
+            SpecificArgs::Goal(_) => Reply::new(GoalAnswer.into()),
+            SpecificArgs::Pomodoro(_) => Reply::new(PomodoroAnswer.into()),
+            SpecificArgs::Program(_) => Reply::new(ProgramAnswer.into())
         };
         let mut state = ActionState::new(reply, &req);
-        let raw_ids = identifiers.iter().map(String::clone);
+        let raw_ids = manager.expand_tag_selectors(identifiers);
         match req.specific_args {
             SpecificArgs::Start(_) => {},
             SpecificArgs::Info(_) => if identifiers.len() == 0 {
@@ -473,7 +1738,36 @@ pub async fn manage(mut manager: Manager, mut req_rx: JobReceiver) {
             SpecificArgs::Play(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, play_action),
             SpecificArgs::Pause(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, pause_action),
             SpecificArgs::Lap(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, lap_action),
-            SpecificArgs::Delete(_) => manager.take_stopwatches_and(&mut state, raw_ids, delete_action)
+            SpecificArgs::Delete(_) => manager.take_stopwatches_and(&mut state, raw_ids, delete_action),
+            SpecificArgs::Timer(_) => {},
+            SpecificArgs::EditLap(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, edit_lap_action),
+            SpecificArgs::Tag(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, tag_action),
+            SpecificArgs::Rename(_) => {},
+            SpecificArgs::Reset(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, reset_action),
+            SpecificArgs::Reopen(_) => if config_rx.borrow().allow_reopen {
+                manager.get_mut_stopwatches_and(&mut state, raw_ids, reopen_action)
+            } else {
+                state.reply.extend_uncollected_errors([(None, reopen_disabled())]);
+            },
+            SpecificArgs::AdjustLap(_) => {
+                manager.get_mut_stopwatches_and(&mut state, raw_ids, adjust_lap_action)
+            },
+            SpecificArgs::AddLap(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, add_lap_action),
+            SpecificArgs::ModifyLap(_) => {
+                manager.get_mut_stopwatches_and(&mut state, raw_ids, modify_lap_action)
+            },
+            SpecificArgs::Run(_) => {},
+            SpecificArgs::Import(_) => {},
+            SpecificArgs::Goal(_) => manager.get_mut_stopwatches_and(&mut state, raw_ids, goal_action),
+            SpecificArgs::Pomodoro(_) => {
+                manager.get_mut_stopwatches_and(&mut state, raw_ids, pomodoro_action)
+            },
+            SpecificArgs::Program(_) => {
+                manager.get_mut_stopwatches_and(&mut state, raw_ids, program_action)
+            }
+        }
+        for (name, attempt) in state.attempts.drain(..) {
+            manager.record_attempt(&name, attempt);
         }
         if let Err(e) = message.res_tx.send(JobResponse { output: state.reply.into() }) {
             error!("{}", e);
@@ -481,5 +1775,275 @@ pub async fn manage(mut manager: Manager, mut req_rx: JobReceiver) {
             debug!("manage just handled a request and sent back a response");
         }
     }
+    // Save once more so that playing laps are saved with the latest time
+    manager.finish_saving().await;
+    if let Err(e) = manager.save() {
+        error!("could not save stopwatches: {}", e);
+    }
     debug!("stop manage");
 }
+
+/// Whether handling `req` can change what gets saved. Looking up a stopwatch
+/// or timer by name or id moves it up the access order, which is saved too,
+/// so only listing every stopwatch, timer or run leaves the state as it is.
+fn changes_state(req: &Request) -> bool {
+    let listing = req.common_args.raw_identifiers.is_empty();
+    match req.specific_args {
+        SpecificArgs::Info(_) => !listing,
+        SpecificArgs::Timer(TimerArgs { action: TimerAction::Info }) => !listing,
+        SpecificArgs::Run(RunArgs { action: RunAction::Info }) => false,
+        _ => true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, time::Duration};
+
+    use stopwatchd::{
+        communication::{
+            client::{CommonArgs, Request},
+            reply_specifics::SpecificAnswer,
+            request_specifics::{IfExists, InfoArgs, StartArgs, TimerAction, TimerArgs}
+        },
+        identifiers::{Identifier, Name, NamePolicy, UniqueId},
+        models::{run::RunAttempt, stopwatch::Stopwatch}
+    };
+
+    use crate::raw_identifier::RawIdentifier;
+
+    use super::{
+        AccessOrder,
+        Manager,
+        ManagerConfig,
+        NameRegistry,
+        changes_state,
+        define_run,
+        sanitize_lookups,
+        start,
+        timer
+    };
+
+    fn start_request(names: &[&str], args: StartArgs) -> Request {
+        let names = names.iter().map(|name| name.to_string()).collect();
+        Request::new(CommonArgs::new(names, false), args.into())
+    }
+
+    #[test]
+    fn test_name_registry() {
+        let mut registry = NameRegistry::new();
+        let policy = NamePolicy::default();
+        let unnamed = Identifier::new(UniqueId::generate(), Name::default());
+        let other_unnamed = Identifier::new(UniqueId::generate(), Name::default());
+        assert_eq!(registry.register(&unnamed), Ok(()));
+        assert_eq!(registry.register(&other_unnamed), Ok(()));
+        assert_eq!(registry.get(&Name::default()), None);
+        assert_eq!(registry.delete(&unnamed), Ok(0));
+
+        let sw1 = Identifier::new(UniqueId::generate(), Name::fixed("sw-1"));
+        registry.register(&sw1).unwrap();
+        assert_eq!(registry.generate_name("sw-{n}", &policy), Ok(Name::fixed("sw-2")));
+
+        // Can't delete a name that belongs to another stopwatch
+        let impostor = Identifier::new(UniqueId::generate(), Name::fixed("sw-1"));
+        assert_eq!(registry.delete(&impostor), Err(sw1.id));
+        assert_eq!(registry.get(&sw1.name), Some(sw1.id));
+        assert_eq!(registry.delete(&sw1), Ok(1));
+        assert_eq!(registry.generate_name("sw-{n}", &policy), Ok(Name::fixed("sw-1")));
+
+        // Gives up instead of searching forever once the numbers don't fit
+        let short = NamePolicy { max_length: 1, ..policy };
+        let mut digits = NameRegistry::new();
+        for _ in 1..10 {
+            let name = digits.generate_name("{n}", &short).unwrap();
+            digits.register(&Identifier::new(UniqueId::generate(), name)).unwrap();
+        }
+        assert!(digits.generate_name("{n}", &short).is_err());
+
+        // Long templates leave room for the number
+        let long = "x".repeat(100) + "-{n}";
+        let first = registry.generate_name(&long, &policy).unwrap();
+        assert_eq!(&*first, "x".repeat(62) + "-1");
+        registry.register(&Identifier::new(UniqueId::generate(), first)).unwrap();
+        assert_eq!(&*registry.generate_name(&long, &policy).unwrap(), "x".repeat(62) + "-2");
+    }
+
+    #[test]
+    fn test_sanitize_lookups() {
+        // `b` was called `+a` before names starting with '+' were fixed
+        let a = Stopwatch::start(Name::fixed("a"));
+        let b = Stopwatch::start(Name::fixed("a"));
+        let mut registry = NameRegistry::new();
+        registry.registry.insert(Name::fixed("a"), a.identifier.id);
+        registry.registry.insert(unsafe { Name::unchecked("+a") }, b.identifier.id);
+        let mut order = AccessOrder::default();
+        let mut stopwatches: HashMap<_, _> = [a, b].into_iter()
+            .map(|sw| (sw.identifier.id, sw))
+            .collect();
+        let ids: Vec<UniqueId> = stopwatches.keys().copied().collect();
+
+        let policy = NamePolicy::default();
+        sanitize_lookups(&mut registry, &mut order, &mut stopwatches, &policy, |sw| {
+            &mut sw.identifier
+        });
+        assert_eq!(registry.registry.len(), 2);
+        assert_eq!(order.len(), 2);
+        for id in ids {
+            assert_eq!(registry.get(&stopwatches[&id].identifier.name), Some(id));
+        }
+
+        // No suffix fits in names of one character
+        let short = NamePolicy { max_length: 1, ..policy };
+        for sw in stopwatches.values_mut() {
+            sw.identifier.name = Name::fixed("a");
+        }
+        let mut registry = NameRegistry::new();
+        sanitize_lookups(&mut registry, &mut order, &mut stopwatches, &short, |sw| {
+            &mut sw.identifier
+        });
+        assert_eq!(registry.registry.len(), 1);
+        assert!(stopwatches.values().any(|sw| sw.identifier.name.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_start_all_or_nothing() {
+        let mut manager = Manager::new();
+        let config = ManagerConfig::default();
+        let args = StartArgs { all_or_nothing: true, ..Default::default() };
+
+        // The same name twice
+        let request = start_request(&["a", "b", "a"], args.clone());
+        let reply = start(&mut manager, &request, &config).await;
+        assert!(reply.successful.is_empty());
+        assert_eq!(reply.errors.len(), 2);
+        assert!(manager.stopwatches.is_empty());
+
+        // A fixed name takes a name given after it
+        let args = StartArgs { fix_bad_names: true, ..args };
+        let reply = start(&mut manager, &start_request(&["@a", "a"], args.clone()), &config).await;
+        assert!(reply.successful.is_empty());
+        assert!(manager.stopwatches.is_empty());
+
+        // Names that only clash once they have been fixed get a suffix
+        let reply = start(&mut manager, &start_request(&["a", "@a", "+a"], args), &config).await;
+        assert!(reply.errors.is_empty());
+        assert_eq!(manager.stopwatches.len(), 3);
+        for name in ["a", "a-2", "a-3"] {
+            assert!(manager.name_registry.get(&Name::fixed(name)).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_if_exists() {
+        let mut manager = Manager::new();
+        let config = ManagerConfig::default();
+        let args = StartArgs::default();
+        start(&mut manager, &start_request(&["a"], args.clone()), &config).await;
+        let a = manager.name_registry.get(&Name::fixed("a")).unwrap();
+
+        // All or nothing holds for every policy
+        for if_exists in [IfExists::Reuse, IfExists::Resume, IfExists::Restart] {
+            let args = StartArgs { all_or_nothing: true, if_exists, ..Default::default() };
+            let reply = start(&mut manager, &start_request(&["a", "b", "b"], args), &config).await;
+            assert!(reply.successful.is_empty());
+            assert_eq!(manager.stopwatches.len(), 1);
+            assert_eq!(manager.name_registry.get(&Name::fixed("a")), Some(a));
+        }
+
+        // Reused stopwatches are keyed like new ones
+        let args = StartArgs { if_exists: IfExists::Reuse, ..args };
+        let reply = start(&mut manager, &start_request(&["a", "c"], args.clone()), &config).await;
+        assert!(reply.successful.contains_key("a"));
+        assert!(reply.successful.contains_key("c"));
+        assert_eq!(reply.successful["a"].identifier.id, a);
+        assert_eq!(manager.name_registry.get(&Name::fixed("a")), Some(a));
+
+        // A name restarted twice in one batch doesn't replace its own stopwatch
+        let args = StartArgs { if_exists: IfExists::Restart, ..args };
+        let reply = start(&mut manager, &start_request(&["a", "a"], args), &config).await;
+        assert_eq!(reply.successful.len(), 1);
+        assert_eq!(reply.errors.len(), 1);
+        let restarted = manager.name_registry.get(&Name::fixed("a")).unwrap();
+        assert_ne!(restarted, a);
+        assert_eq!(manager.stopwatches.len(), 2);
+
+        // Resuming doesn't reopen stopwatches if swd.toml says not to
+        manager.stopwatches.get_mut(&restarted).unwrap().end();
+        let no_reopen = ManagerConfig { allow_reopen: false, ..config };
+        let args = StartArgs { if_exists: IfExists::Resume, ..Default::default() };
+        let reply = start(&mut manager, &start_request(&["a"], args), &no_reopen).await;
+        assert!(reply.successful.is_empty());
+        assert_eq!(reply.errors.len(), 1);
+        assert!(manager.stopwatches[&restarted].state().ended());
+    }
+
+    #[test]
+    fn test_changes_state() {
+        let info = |names: &[&str]| {
+            let names = names.iter().map(|name| name.to_string()).collect();
+            Request::new(CommonArgs::new(names, false), InfoArgs.into())
+        };
+        assert!(!changes_state(&info(&[])));
+        // Looking a stopwatch up moves it up the access order
+        assert!(changes_state(&info(&["a"])));
+        assert!(changes_state(&start_request(&[], StartArgs::default())));
+    }
+
+    #[test]
+    fn test_define_run() {
+        let mut manager = Manager::new();
+        let policy = NamePolicy::default();
+        let segments = vec!["first".to_string(), "second".to_string()];
+        define_run(&mut manager, &policy, " any% ", &segments, false).unwrap();
+        manager.record_attempt("any%", RunAttempt { start: None, segments: vec![Duration::from_secs(5); 2] });
+
+        assert!(define_run(&mut manager, &policy, "any%", &segments, false).is_err());
+        assert!(define_run(&mut manager, &policy, "a\nb", &segments, false).is_err());
+        let kept = define_run(&mut manager, &policy, "any%", &segments, true).unwrap();
+        assert_eq!(kept.attempts.len(), 1);
+        assert_eq!(kept.personal_best(), Some(Duration::from_secs(10)));
+        let replaced = define_run(&mut manager, &policy, "any%", &segments[..1], true).unwrap();
+        assert!(replaced.attempts.is_empty());
+        assert_eq!(manager.runs["any%"], replaced);
+    }
+
+    #[test]
+    fn test_timer_start() {
+        let mut manager = Manager::new();
+        let policy = NamePolicy::default();
+        let timer_request = |names: &[&str], fix_bad_names| {
+            let names = names.iter().map(|name| name.to_string()).collect();
+            let action = TimerAction::Start { target: Duration::from_secs(600), fix_bad_names };
+            Request::new(CommonArgs::new(names, false), TimerArgs { action }.into())
+        };
+
+        let reply = timer(&mut manager, &timer_request(&["tea", "coffee", "a\nb"], false), &policy);
+        assert_eq!(manager.timers.len(), 2);
+        assert!(reply.errors.contains_key(&Some("a\nb".to_string())));
+        match reply.specific_answer {
+            SpecificAnswer::Timer(answer) => assert_eq!(answer.timers.len(), 2),
+            _ => panic!("timer should reply with SpecificAnswer::Timer")
+        }
+
+        let reply = timer(&mut manager, &timer_request(&["tea", "tea\n"], true), &policy);
+        assert!(reply.errors.contains_key(&Some("tea".to_string())));
+        assert!(manager.timer_name_registry.get(&Name::fixed("tea-2")).is_some());
+        assert_eq!(manager.timers.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_normalized_name() {
+        let mut manager = Manager::new();
+        let config = ManagerConfig::default();
+        start(&mut manager, &start_request(&[" caf\u{e9}"], StartArgs::default()), &config).await;
+        let id = manager.name_registry.get(&Name::fixed("caf\u{e9}")).unwrap();
+
+        // "e" followed by a combining acute accent
+        for raw in ["caf\u{e9}", " caf\u{e9}", "cafe\u{301} "] {
+            let (sw, _) = manager.get_stopwatch(&RawIdentifier::new(raw)).unwrap();
+            assert_eq!(sw.identifier.id, id);
+        }
+        let renamed = manager.rename_stopwatch(&RawIdentifier::new("cafe\u{301}"), Name::fixed("tea"));
+        assert_eq!(renamed.map(|sw| sw.identifier.id), Ok(id));
+    }
+}
@@ -19,14 +19,17 @@ use stopwatchd::{
     runtime::{runtime_dir, get_uid, server_socket_path},
     logging
 };
-use tokio::net::UnixListener;
+use tokio::{net::UnixListener, sync::watch};
 
 use crate::{
     cleanup::Cleanup,
     signal::{make_signal_handler, close_signal_handler},
     socket::{clear_socket, create_socket, listen_to_socket, set_socket_perms},
     manager::{Manager, make_request_channels, manage, JobSender},
+    storage::state_file_path
 };
+#[cfg(feature = "swd-config")]
+use crate::manager::ConfigSender;
 
 mod cleanup;
 mod config;
@@ -35,6 +38,7 @@ mod manager;
 mod raw_identifier;
 mod signal;
 mod socket;
+mod storage;
 mod utils;
 
 #[tokio::main]
@@ -56,12 +60,17 @@ async fn main() {
     let rt_dir = runtime_dir(uid);
     debug!("setting up runtime directory: {:?}", rt_dir);
     create_dir_all(rt_dir).unwrap();
+    let data_dir = cli.data_dir(uid);
+    debug!("setting up data directory: {:?}", data_dir);
+    create_dir_all(&data_dir).unwrap();
 
     // Start stopwatch manager
     // Must come before interrupt handler for some reason
-    let manager = Manager::new();
+    let manager_config = cli.manager_config();
+    let manager = Manager::restore(state_file_path(data_dir), &manager_config.name_policy);
     let (req_tx, req_rx) = make_request_channels();
-    let manager_handle = tokio::spawn(manage(manager, req_rx));
+    let (config_tx, config_rx) = watch::channel(manager_config);
+    let manager_handle = tokio::spawn(manage(manager, req_rx, config_rx));
 
     { // PID File
         debug!("setting up pidfile");
@@ -83,11 +92,12 @@ async fn main() {
     #[cfg(not(feature = "swd-config"))]
     run(&socket, &req_tx).await;
     #[cfg(feature = "swd-config")]
-    run(&socket, &req_tx, &cli.config_path).await;
+    run(&socket, &req_tx, &config_tx, &cli.config_path).await;
 
     // Clean up manager
     debug!("cleaning up manager");
     drop(req_tx); // Force close manager_handle
+    drop(config_tx);
     manager_handle.await.unwrap();
     
     // Clean up
@@ -111,7 +121,12 @@ async fn run(socket: &UnixListener, req_tx: &JobSender) {
 }
 
 #[cfg(feature = "swd-config")]
-async fn run(socket: &UnixListener, req_tx: &JobSender, config_path: &str) {
+async fn run(
+    socket: &UnixListener,
+    req_tx: &JobSender,
+    config_tx: &ConfigSender,
+    config_path: &str
+) {
     let restart = Arc::new(AtomicBool::new(true));
     // Application
     while restart.load(Ordering::Relaxed) {
@@ -132,6 +147,7 @@ async fn run(socket: &UnixListener, req_tx: &JobSender, config_path: &str) {
             cli.supplement_file(Some(config_path)).unwrap();
             log::set_max_level(cli.log_level().into());
             info!("logging started");
+            config_tx.send_replace(cli.manager_config());
         }
     }
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use std::ops::Deref;
 use serde::{Serialize, Deserialize};
 use stopwatchd::{
     util::{raw_identifier_to_uuid_node, get_uuid_node},
-    identifiers::{Identifier, UniqueId, Name}
+    identifiers::{Identifier, UniqueId, Name, NamePolicy}
 };
 use uuid::Uuid;
 
@@ -48,11 +48,12 @@ impl RawIdentifier {
     }
 
     /// Resolve this [`RawIdentifier`] into a [`UniqueId`] if possible.
-    /// Otherwise, return a [`Name`] instead.
-    pub fn to_possible_id_or_name(self) -> Result<UniqueId, Name> {
+    /// Otherwise, return a [`Name`] normalized and trimmed by `policy`
+    /// instead.
+    pub fn to_possible_id_or_name(self, policy: &NamePolicy) -> Result<UniqueId, Name> {
         match self.get_possible_id() {
             Some(id) => Ok(id),
-            None => Err(unsafe { Name::unchecked(self.raw) })
+            None => Err(policy.lookup(&self.raw))
         }
     }
 
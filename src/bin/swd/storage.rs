@@ -0,0 +1,144 @@
+//! Save stopwatches and timers to disk so that they survive `swd` restarts.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf}
+};
+
+use ciborium::{ser::into_writer, de::from_reader, value::Value};
+use serde::{Serialize, Deserialize};
+use stopwatchd::models::{
+    run::Run,
+    snapshot::{StopwatchSnapshot, TimerSnapshot},
+    stopwatch::Stopwatch,
+    timer::Timer
+};
+
+use crate::manager::{AccessOrder, NameRegistry};
+
+/// Name of the file in the data directory that stores the state of `swd`.
+pub const STATE_FILE_NAME: &str = "state.cbor";
+
+/// Get path to the state file inside `data_dir`.
+pub fn state_file_path<P: AsRef<Path>>(data_dir: P) -> PathBuf {
+    data_dir.as_ref().join(STATE_FILE_NAME)
+}
+
+/// Everything [`Manager`](crate::manager::Manager) needs to pick up where it
+/// left off.
+#[derive(Debug)]
+pub struct SavedState {
+    pub stopwatches: Vec<Stopwatch>,
+    pub access_order: AccessOrder,
+    pub name_registry: NameRegistry,
+    pub timers: Vec<Timer>,
+    pub timer_access_order: AccessOrder,
+    pub timer_name_registry: NameRegistry,
+    pub runs: BTreeMap<String, Run>
+}
+
+/// [`SavedState`] as it is stored in the state file. Stopwatches are kept as
+/// raw values until they have been migrated to the current snapshot schema.
+#[derive(Debug, Deserialize)]
+struct RawSavedState {
+    stopwatches: Vec<Value>,
+    access_order: AccessOrder,
+    name_registry: NameRegistry,
+    #[serde(default)]
+    timers: Vec<Value>,
+    #[serde(default)]
+    timer_access_order: AccessOrder,
+    #[serde(default)]
+    timer_name_registry: NameRegistry,
+    #[serde(default)]
+    runs: BTreeMap<String, Run>
+}
+
+impl TryFrom<RawSavedState> for SavedState {
+    type Error = io::Error;
+
+    fn try_from(raw: RawSavedState) -> io::Result<Self> {
+        let stopwatches = raw.stopwatches.into_iter()
+            .map(|sw| StopwatchSnapshot::from_value(sw).map(StopwatchSnapshot::restore))
+            .collect::<Result<_, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let timers = raw.timers.into_iter()
+            .map(|t| TimerSnapshot::from_value(t).map(TimerSnapshot::restore))
+            .collect::<Result<_, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self {
+            stopwatches,
+            access_order: raw.access_order,
+            name_registry: raw.name_registry,
+            timers,
+            timer_access_order: raw.timer_access_order,
+            timer_name_registry: raw.timer_name_registry,
+            runs: raw.runs
+        })
+    }
+}
+
+/// Borrowed version of [`SavedState`] so that the manager doesn't have to be
+/// copied to be saved.
+#[derive(Debug, Serialize)]
+pub struct SavedStateRef<'m> {
+    pub stopwatches: Vec<StopwatchSnapshot>,
+    pub access_order: &'m AccessOrder,
+    pub name_registry: &'m NameRegistry,
+    pub timers: Vec<TimerSnapshot>,
+    pub timer_access_order: &'m AccessOrder,
+    pub timer_name_registry: &'m NameRegistry,
+    pub runs: &'m BTreeMap<String, Run>
+}
+
+/// Read the state file at `path`. If there is no state file, [`None`] is
+/// returned.
+///
+/// If any stopwatch can't be migrated to the current snapshot schema, the
+/// whole file is treated as unreadable so that nothing gets lost when the
+/// state is saved again.
+pub fn load_state<P: AsRef<Path>>(path: P) -> io::Result<Option<SavedState>> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e)
+    };
+    let raw: RawSavedState = from_reader(BufReader::new(file))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    raw.try_into().map(Some)
+}
+
+/// Write `state` to the state file at `path`.
+pub fn save_state<P: AsRef<Path>>(path: P, state: &SavedStateRef<'_>) -> io::Result<()> {
+    write_state(path, &encode_state(state)?)
+}
+
+/// Serialize `state` the way it is stored in the state file.
+pub fn encode_state(state: &SavedStateRef<'_>) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    into_writer(state, &mut bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(bytes)
+}
+
+/// Write state from [`encode_state`] to the state file at `path`.
+///
+/// The state is written to a temporary file first, which then replaces the
+/// old state file so that a crash halfway through doesn't corrupt it.
+pub fn write_state<P: AsRef<Path>>(path: P, bytes: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, bytes)?;
+    fs::rename(tmp_path, path)
+}
+
+/// Move an unreadable state file out of the way so that it doesn't get
+/// overwritten.
+pub fn quarantine_state<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let bad_path = path.with_extension("bad");
+    fs::rename(path, &bad_path)?;
+    Ok(bad_path)
+}
@@ -10,7 +10,7 @@ use stopwatchd::{
 use crate::{
     cli,
     keypress::{keypress_detector, KeypressReceiver, keypress_timeout},
-    ui::{color::init_color, Ui}
+    ui::{color::init_color, prompt::PromptPurpose, Ui}
 };
 
 pub async fn start() {
@@ -62,7 +62,7 @@ pub async fn start() {
     // start stopwatch if --new passed
     if let Some(name) = cli.new_stopwatch {
         ui.prompt_state.name = name.clone();
-        ui.start_stopwatch().await;
+        ui.start_stopwatch(cli.paused).await;
         ui.set_focus_raw_identifier(&name).await;
     } else if let Some(raw) = cli.focus_stopwatch {
         ui.set_focus_raw_identifier(&raw).await;
@@ -115,7 +115,11 @@ pub async fn handle_keypress(ui: &mut Ui, ch: pancurses::Input) -> bool {
             },
             pancurses::Input::Character('\n') => {
                 ui.prompt_state.visible = false;
-                ui.start_stopwatch().await;
+                match ui.prompt_state.purpose {
+                    PromptPurpose::NewStopwatch => ui.start_stopwatch(false).await,
+                    PromptPurpose::NewPausedStopwatch => ui.start_stopwatch(true).await,
+                    PromptPurpose::Rename => ui.rename_stopwatch().await
+                }
                 ui.prompt_state.reset();
             },
             pancurses::Input::Character(c) => {
@@ -161,16 +165,25 @@ pub async fn handle_keypress(ui: &mut Ui, ch: pancurses::Input) -> bool {
                 ui.toggle_state().await;
             },
             pancurses::Input::Character('n') if !ui.is_focus_active() => {
-                ui.prompt_name();
+                ui.prompt_name(false);
+            },
+            pancurses::Input::Character('p') if !ui.is_focus_active() => {
+                ui.prompt_name(true);
             },
             pancurses::Input::Character('s') if ui.is_focus_active() => {
                 ui.stop_stopwatch().await;
             },
             pancurses::Input::Character('\n') if ui.is_focus_active() => {
-                ui.lap_stopwatch().await;
+                ui.lap_stopwatch(false).await;
+            },
+            pancurses::Input::Character('l') if ui.is_focus_active() => {
+                ui.lap_stopwatch(true).await;
             },
             pancurses::Input::Character('d') if ui.is_focus_active() => {
                 ui.delete_stopwatch().await;
+            },
+            pancurses::Input::Character('r') if ui.is_focus_active() => {
+                ui.prompt_rename();
             }
             _ => {}
         }
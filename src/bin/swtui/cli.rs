@@ -9,6 +9,10 @@ pub struct Cli {
     #[arg(long = "new", global = true)]
     pub new_stopwatch: Option<String>,
 
+    /// Don't play the stopwatch created by `--new`.
+    #[arg(long = "paused", global = true, requires = "new_stopwatch")]
+    pub paused: bool,
+
     /// Focus on a stopwatch on startup.
     #[arg(short = 'f', long = "focus", global = true)]
     pub focus_stopwatch: Option<String>,
@@ -54,6 +54,12 @@ impl Bar {
         ColorPair::Bar.set_color(&ui.window, false);
         *x = ui.add_string(*x, y, "New ");
 
+        // P: New Paused
+        ColorPair::BarKey.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "P");
+        ColorPair::Bar.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "New Paused ");
+
         self.draw_global_shortcuts(ui, x);
     }
 
@@ -99,6 +105,12 @@ impl Bar {
         ColorPair::Bar.set_color(&ui.window, false);
         *x = ui.add_string(*x, y, "New Lap ");
 
+        // L: Lap without playing
+        ColorPair::BarKey.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "L");
+        ColorPair::Bar.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "Lap Paused ");
+
         // S: Stop
         ColorPair::BarKey.set_color(&ui.window, false);
         *x = ui.add_string(*x, y, "S");
@@ -111,6 +123,12 @@ impl Bar {
         ColorPair::Bar.set_color(&ui.window, false);
         *x = ui.add_string(*x, y, "Delete ");
 
+        // R: Rename
+        ColorPair::BarKey.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "R");
+        ColorPair::Bar.set_color(&ui.window, false);
+        *x = ui.add_string(*x, y, "Rename ");
+
         self.draw_global_shortcuts(ui, x);
     }
 
@@ -10,7 +10,11 @@ pub enum ColorPair {
     /// Colour of the bar
     Bar = 4,
     /// Colour of the keyboard shortcut in the bar
-    BarKey = 5
+    BarKey = 5,
+    /// A lap that was faster than another lap
+    Faster = 6,
+    /// A lap that was slower than another lap
+    Slower = 7
 }
 
 impl ColorPair {
@@ -43,4 +47,6 @@ pub fn init_color() {
     pancurses::init_pair(ColorPair::Selected as i16, pancurses::COLOR_CYAN, bg);
     pancurses::init_pair(ColorPair::Bar as i16, pancurses::COLOR_BLACK, pancurses::COLOR_CYAN);
     pancurses::init_pair(ColorPair::BarKey as i16, pancurses::COLOR_WHITE, bg);
+    pancurses::init_pair(ColorPair::Faster as i16, pancurses::COLOR_GREEN, bg);
+    pancurses::init_pair(ColorPair::Slower as i16, pancurses::COLOR_RED, bg);
 }
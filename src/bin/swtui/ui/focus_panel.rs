@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
 use stopwatchd::{
-    communication::details::StopwatchDetails,
+    communication::details::{GoalProgress, LapDelta, StopwatchDetails},
     identifiers::Identifier
 };
 
 use crate::{
     ui::{Ui, color::ColorPair, geometry::BordersGeometry},
-    util::center_text
+    util::{center_text, progress_bar}
 };
 
 pub struct FocusPanel {
@@ -85,28 +85,122 @@ impl FocusPanel {
             let lap_count = format!("Lap Count: {}", d.laps_count());
             let (l_x, r_x) = center_text(lap_count.len(), (left, right)).unwrap();
             self.window.mvaddnstr(top+4, l_x, &lap_count, r_x - l_x + 1);
+            let mut row = top + 5;
+
+            // Policy for starting new laps
+            if let Some(auto_lap) = d.auto_lap {
+                let text = format!("Auto Lap: {}", auto_lap);
+                let (l_x, r_x) = center_text(text.len(), (left, right)).unwrap();
+                self.window.mvaddnstr(row, l_x, &text, r_x - l_x + 1);
+                row += 1;
+            }
+
+            // How a program finished
+            if let Some(outcome) = d.program.as_ref().and_then(|p| p.outcome) {
+                let text = format!("Program {}", outcome);
+                let (l_x, r_x) = center_text(text.len(), (left, right)).unwrap();
+                ColorPair::Active.set_color(&self.window, true);
+                self.window.mvaddnstr(row, l_x, &text, r_x - l_x + 1);
+                row += 1;
+            }
+
+            // Progress towards the goals of the stopwatch and current lap, and
+            // through the current pomodoro phase or program step
+            let phase = d.pomodoro.map(|p| format!("Phase ({}, {} done)", p.phase, p.cycles));
+            let step = d.program.as_ref().and_then(|p| {
+                let name = &p.current_step()?.name;
+                Some(format!("Step {}/{} ({})", p.step + 1, p.program.steps.len(), name))
+            });
+            let progress = [
+                (Some("Goal".to_string()), d.goal),
+                (Some("Lap Goal".to_string()), d.lap_goal),
+                (phase, d.phase_progress()),
+                (step, d.step_progress())
+            ];
+            for (name, goal) in progress {
+                let (name, goal) = match (name, goal) {
+                    (Some(name), Some(goal)) => (name, goal),
+                    _ => continue
+                };
+                let text = format!("{}: {}", name, goal.format(&ui.formatter));
+                let (l_x, r_x) = center_text(text.len(), (left, right)).unwrap();
+                ColorPair::Active.set_color(&self.window, false);
+                self.window.mvaddnstr(row, l_x, &text, r_x - l_x + 1);
+                row += 1;
+                let bar = progress_bar(goal.fraction(), (right - left + 1) as usize);
+                let color = if goal.reached() { ColorPair::Slower } else { ColorPair::Faster };
+                color.set_color(&self.window, false);
+                self.window.mvaddnstr(row, left, &bar, right - left + 1);
+                row += 1;
+            }
+
+            // Lap statistics if any laps have finished
+            if let Some(ref stats) = d.lap_stats {
+                ColorPair::Active.set_color(&self.window, false);
+                let f = &ui.formatter;
+                let lines = [
+                    format!("Fastest: {} (lap {})", f.format_duration(stats.min), stats.fastest),
+                    format!("Slowest: {} (lap {})", f.format_duration(stats.max), stats.slowest),
+                    format!(
+                        "Mean: {} Median: {}",
+                        f.format_duration(stats.mean),
+                        f.format_duration(stats.median)
+                    ),
+                    format!("Std Dev: {}", f.format_duration(stats.std_dev))
+                ];
+                for line in lines {
+                    let (l_x, r_x) = center_text(line.len(), (left, right)).unwrap();
+                    self.window.mvaddnstr(row, l_x, &line, r_x - l_x + 1);
+                    row += 1;
+                }
+            }
 
             // Display all laps if exists
             if let Some(ref vi) = d.verbose_info {
                 ColorPair::Active.set_color(&self.window, true);
                 let display_laps = "Laps:";
                 let (l_x, r_x) = center_text(display_laps.len(), (left, right)).unwrap();
-                self.window.mvaddnstr(top+5, l_x, display_laps, r_x - l_x + 1);
+                self.window.mvaddnstr(row, l_x, display_laps, r_x - l_x + 1);
 
-                ColorPair::Active.set_color(&self.window, false);
-                let mut row = top + 6;
+                row += 1;
                 let lap_scroll = ui.focus_panel_state.lap_scroll;
+                let splits = d.lap_splits().unwrap_or_default();
                 // latest laps first
-                for (index, lap) in vi.laps.iter().rev().skip(lap_scroll).enumerate() {
+                for (index, (lap, split)) in vi.laps.iter()
+                    .zip(&splits)
+                    .rev()
+                    .skip(lap_scroll)
+                    .enumerate()
+                {
                     if row > bottom {
                         break;
                     }
 
                     let lap_number = d.laps_count() - index - lap_scroll;
                     let lap_time = ui.formatter.format_duration(lap.duration);
-                    let display_lap = format!("{}: {}", lap_number, lap_time);
-                    let (l_x, r_x) = center_text(display_lap.len(), (left, right)).unwrap();
-                    self.window.mvaddnstr(row, l_x, &display_lap, r_x - l_x + 1);
+                    let mut parts = vec![(format!("{}: {}", lap_number, lap_time), ColorPair::Active)];
+                    // Deltas to the previous and best laps
+                    for delta in [split.to_previous, split.to_best].into_iter().flatten() {
+                        let color = match delta {
+                            LapDelta::Faster(_) => ColorPair::Faster,
+                            LapDelta::Slower(_) => ColorPair::Slower,
+                            LapDelta::Even => ColorPair::Active
+                        };
+                        parts.push((delta.format(&ui.formatter), color));
+                    }
+                    if let Some(ref label) = lap.label {
+                        parts.push((label.clone(), ColorPair::Active));
+                    }
+                    let length = parts.iter().map(|(part, _)| part.len() + 1).sum::<usize>() - 1;
+                    let (mut x, r_x) = center_text(length, (left, right)).unwrap();
+                    for (part, color) in parts {
+                        if x > r_x {
+                            break;
+                        }
+                        color.set_color(&self.window, false);
+                        self.window.mvaddnstr(row, x, &part, r_x - x + 1);
+                        x += part.len() as i32 + 1;
+                    }
 
                     row += 1;
                 }
@@ -155,6 +249,24 @@ impl FocusPanelState {
     pub fn update(&mut self, details: Option<StopwatchDetails>) {
         match details {
             Some(d) => {
+                // Ring the bell when the same stopwatch crosses one of its goals
+                // or moves on to the next pomodoro phase or program step
+                if let Some(ref old) = self.details {
+                    let crossed = |old: Option<GoalProgress>, new: Option<GoalProgress>| {
+                        matches!((old, new), (Some(o), Some(n)) if !o.reached() && n.reached())
+                    };
+                    let next_phase = matches!(
+                        (old.pomodoro, d.pomodoro),
+                        (Some(o), Some(n)) if o.phase != n.phase
+                    ) || matches!(
+                        (&old.program, &d.program),
+                        (Some(o), Some(n)) if o.step != n.step || o.outcome != n.outcome
+                    );
+                    let goal_reached = crossed(old.goal, d.goal) || crossed(old.lap_goal, d.lap_goal);
+                    if old.identifier.id == d.identifier.id && (goal_reached || next_phase) {
+                        pancurses::beep();
+                    }
+                }
                 self.update_selected(Some(d.identifier.clone()));
                 self.details = Some(d);
             },
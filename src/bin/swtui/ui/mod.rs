@@ -13,9 +13,9 @@ use std::{
 
 use stopwatchd::{
     communication::{
-        client::{ClientSender, Request},
+        client::{ClientSender, CommonArgs, Request},
         reply_specifics::{InfoAnswer, SpecificAnswer},
-        request_specifics::StartArgs,
+        request_specifics::{LapArgs, StartArgs, RenameArgs},
     },
     fmt::Formatter,
     models::stopwatch::State
@@ -27,7 +27,7 @@ use self::{
     geometry::{Size, BordersGeometry, BarLocation},
     list_panel::{ListPanel, ListPanelState},
     focus_panel::{FocusPanel, FocusPanelState},
-    prompt::{Prompt, PromptState}
+    prompt::{Prompt, PromptState, PromptPurpose}
 };
 
 pub struct Ui {
@@ -227,13 +227,31 @@ impl Ui {
         }
     }
 
-    pub fn prompt_name(&mut self) {
+    pub fn prompt_name(&mut self, paused: bool) {
+        // Don't suggest the name left over from renaming
+        if self.prompt_state.purpose == PromptPurpose::Rename {
+            self.prompt_state.reset();
+        }
+        self.prompt_state.purpose = if paused {
+            PromptPurpose::NewPausedStopwatch
+        } else {
+            PromptPurpose::NewStopwatch
+        };
         self.prompt_state.visible = true;
     }
 
-    pub async fn start_stopwatch(&mut self) {
+    pub fn prompt_rename(&mut self) {
+        if let Some(ref d) = self.focus_panel_state.details {
+            self.prompt_state.name = d.identifier.name.to_string();
+            self.prompt_state.purpose = PromptPurpose::Rename;
+            self.prompt_state.visible = true;
+        }
+    }
+
+    pub async fn start_stopwatch(&mut self, paused: bool) {
         let name = self.prompt_state.name.clone();
-        let request = Request::start(vec![name], true, StartArgs { fix_bad_names: true });
+        let args = StartArgs { fix_bad_names: true, paused, ..Default::default() };
+        let request = Request::start(vec![name], true, args);
         
         let reply = ClientSender::new(&self.ssock_path).send(request).await.unwrap();
 
@@ -244,6 +262,33 @@ impl Ui {
         self.refresh_list().await;
     }
 
+    pub async fn rename_stopwatch(&mut self) {
+        let (mut reply, raw_id) = if let Some(ref d) = self.focus_panel_state.details {
+            let raw_id = d.identifier.to_string();
+            let args = RenameArgs { name: self.prompt_state.name.clone(), fix_bad_names: true };
+            let request = Request::rename(raw_id.clone(), true, args);
+
+            let reply = ClientSender::new(&self.ssock_path).send(request).await.unwrap();
+
+            if !reply.errors.is_empty() {
+                error!("[swtui::ui::Ui::rename_stopwatch] uh oh");
+            }
+
+            (reply, raw_id)
+        } else {
+            return;
+        };
+
+        if let SpecificAnswer::Rename(_) = reply.specific_answer {
+            if let Some(details) = reply.successful.remove(&raw_id) {
+                self.focus_panel_state.update(Some(details));
+            }
+        } else {
+            panic!("server did not reply with SpecificAnswer::Rename!");
+        }
+        self.refresh_list().await;
+    }
+
     pub async fn stop_stopwatch(&mut self) {
         let (mut reply, identifier) = if let Some(ref mut d) = self.focus_panel_state.details {
             let request = match d.state {
@@ -272,12 +317,12 @@ impl Ui {
         }
     }
 
-    pub async fn lap_stopwatch(&mut self) {
+    pub async fn lap_stopwatch(&mut self, paused: bool) {
         let (mut reply, identifier) = if let Some(ref mut d) = self.focus_panel_state.details {
             let request = match d.state {
-                State::Playing | State::Paused => Request::lap(
-                    vec![d.identifier.to_string()],
-                    true
+                State::Playing | State::Paused => Request::new(
+                    CommonArgs::new(vec![d.identifier.to_string()], true),
+                    LapArgs { paused, ..Default::default() }.into()
                 ),
                 State::Ended => return ()
             };
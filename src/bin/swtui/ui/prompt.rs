@@ -47,7 +47,7 @@ impl Prompt {
         self.window.refresh();
         self.border(ui);
         ColorPair::Active.set_color(&self.window, true);
-        self.window.mvaddstr(1, 1, "Name for stopwatch:");
+        self.window.mvaddstr(1, 1, ui.prompt_state.purpose.title());
         // TODO: CJK characters occupy 2 cells each according to ncurses!
         let chars: Vec<char> = ui.prompt_state.name.chars().collect();
         // let chars = &ui.prompt_state.name;
@@ -67,16 +67,36 @@ impl Prompt {
     }
 }
 
+/// What the name typed into the [`Prompt`] is for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PromptPurpose {
+    #[default]
+    NewStopwatch,
+    NewPausedStopwatch,
+    Rename
+}
+
+impl PromptPurpose {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::NewStopwatch => "Name for stopwatch:",
+            Self::NewPausedStopwatch => "Name for paused stopwatch:",
+            Self::Rename => "New name for stopwatch:"
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PromptState {
     pub name: String,
-    pub visible: bool
+    pub visible: bool,
+    pub purpose: PromptPurpose
 }
 
 impl PromptState {
     pub fn new(name: impl Into<String>, visible: bool) -> Self {
         let name = name.into();
-        Self { name, visible }
+        Self { name, visible, purpose: PromptPurpose::default() }
     }
 
     pub fn reset(&mut self) {
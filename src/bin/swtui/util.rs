@@ -38,9 +38,21 @@ pub fn center_text(
     Some((min_x, max_x))
 }
 
+/// Draw a progress bar that is `width` characters wide, including the
+/// brackets on either side. `fraction` is clamped between 0.0 and 1.0.
+/// Nothing is drawn if `width` leaves no room for the brackets.
+pub fn progress_bar(fraction: f64, width: usize) -> String {
+    if width < 2 {
+        return String::new();
+    }
+    let inner = width - 2;
+    let filled = ((fraction.clamp(0.0, 1.0) * inner as f64).round() as usize).min(inner);
+    format!("[{}{}]", "#".repeat(filled), " ".repeat(inner - filled))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::util::center_text;
+    use crate::util::{center_text, progress_bar};
 
     #[test]
     fn test_center_text() {
@@ -51,4 +63,13 @@ mod tests {
         assert_eq!(center_text(100, (0, 4)), Some((0, 4)));
         assert_eq!(center_text(6, (0, 4)), Some((0, 4)));
     }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(0.5, 6), "[##  ]");
+        assert_eq!(progress_bar(0.0, 4), "[  ]");
+        assert_eq!(progress_bar(2.5, 4), "[##]");
+        assert_eq!(progress_bar(0.5, 2), "[]");
+        assert_eq!(progress_bar(0.5, 1), "");
+    }
 }
@@ -11,7 +11,11 @@ use tokio::net::UnixStream;
 use crate::{util::iter_into_vec, traits::Codecable};
 
 use super::{
-    request_specifics::{DeleteArgs, InfoArgs, LapArgs, PauseArgs, PlayArgs, StartArgs, StopArgs},
+    request_specifics::{
+        AddLapArgs, AdjustLapArgs, DeleteArgs, EditLapArgs, GoalArgs, ImportArgs, InfoArgs,
+        LapArgs, ModifyLapArgs, PauseArgs, PlayArgs, PomodoroArgs, ProgramArgs, RenameArgs,
+        ReopenArgs, ResetArgs, RunArgs, StartArgs, StopArgs, TagArgs, TimerArgs
+    },
     server::Reply
 };
 pub use super::request_specifics::SpecificArgs;
@@ -96,25 +100,25 @@ impl Request {
 
     pub fn stop(raw_identifiers: Vec<String>, verbose: bool) -> Self {
         let common_args = CommonArgs::new(raw_identifiers, verbose);
-        let specific_args = SpecificArgs::Stop(StopArgs);
+        let specific_args = SpecificArgs::Stop(StopArgs::default());
         Self::new(common_args, specific_args)
     }
 
     pub fn play(raw_identifiers: Vec<String>, verbose: bool) -> Self {
         let common_args = CommonArgs::new(raw_identifiers, verbose);
-        let specific_args = SpecificArgs::Play(PlayArgs);
+        let specific_args = SpecificArgs::Play(PlayArgs::default());
         Self::new(common_args, specific_args)
     }
 
     pub fn pause(raw_identifiers: Vec<String>, verbose: bool) -> Self {
         let common_args = CommonArgs::new(raw_identifiers, verbose);
-        let specific_args = SpecificArgs::Pause(PauseArgs);
+        let specific_args = SpecificArgs::Pause(PauseArgs::default());
         Self::new(common_args, specific_args)
     }
 
     pub fn lap(raw_identifiers: Vec<String>, verbose: bool) -> Self {
         let common_args = CommonArgs::new(raw_identifiers, verbose);
-        let specific_args = SpecificArgs::Lap(LapArgs);
+        let specific_args = SpecificArgs::Lap(LapArgs::default());
         Self::new(common_args, specific_args)
     }
 
@@ -124,6 +128,90 @@ impl Request {
         Self::new(common_args, specific_args)
     }
 
+    pub fn edit_lap(raw_identifiers: Vec<String>, verbose: bool, args: EditLapArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::EditLap(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn adjust_lap(raw_identifiers: Vec<String>, verbose: bool, args: AdjustLapArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::AdjustLap(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn add_lap(raw_identifier: String, verbose: bool, args: AddLapArgs) -> Self {
+        let common_args = CommonArgs::new(vec![raw_identifier], verbose);
+        let specific_args = SpecificArgs::AddLap(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn modify_lap(raw_identifier: String, verbose: bool, args: ModifyLapArgs) -> Self {
+        let common_args = CommonArgs::new(vec![raw_identifier], verbose);
+        let specific_args = SpecificArgs::ModifyLap(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn tag(raw_identifiers: Vec<String>, verbose: bool, args: TagArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Tag(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn rename(raw_identifier: String, verbose: bool, args: RenameArgs) -> Self {
+        let common_args = CommonArgs::new(vec![raw_identifier], verbose);
+        let specific_args = SpecificArgs::Rename(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn goal(raw_identifiers: Vec<String>, verbose: bool, args: GoalArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Goal(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn pomodoro(raw_identifiers: Vec<String>, verbose: bool, args: PomodoroArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Pomodoro(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn program(raw_identifiers: Vec<String>, verbose: bool, args: ProgramArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Program(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn reset(raw_identifiers: Vec<String>, verbose: bool, args: ResetArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Reset(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn reopen(raw_identifiers: Vec<String>, verbose: bool, args: ReopenArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Reopen(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn timer(raw_identifiers: Vec<String>, verbose: bool, args: TimerArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Timer(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn import(raw_identifiers: Vec<String>, verbose: bool, args: ImportArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Import(args);
+        Self::new(common_args, specific_args)
+    }
+
+    pub fn run(raw_identifiers: Vec<String>, verbose: bool, args: RunArgs) -> Self {
+        let common_args = CommonArgs::new(raw_identifiers, verbose);
+        let specific_args = SpecificArgs::Run(args);
+        Self::new(common_args, specific_args)
+    }
+
     /// Send this [`Request`] through a socket to `swd`. A [`UnixStream`] is
     /// returned so that a reply can be read from it.
     pub async fn send_to_socket<P: AsRef<Path>>(&self, ssock_path: P) -> io::Result<UnixStream> {
@@ -182,12 +270,13 @@ mod test {
             client::{Request, CommonArgs},
             request_specifics::StartArgs
         },
+        identifiers::Tag,
         traits::Codecable
     };
 
     #[test]
     fn test_cycle_0() {
-        let specific = StartArgs { fix_bad_names: false }.into();
+        let specific = StartArgs { fix_bad_names: false, ..Default::default() }.into();
         let common = CommonArgs::from_iter([""], false);
         let request = Request::new(common, specific);
 
@@ -199,7 +288,11 @@ mod test {
 
     #[test]
     fn test_cycle_1() {
-        let specific = StartArgs { fix_bad_names: true }.into();
+        let specific = StartArgs {
+            fix_bad_names: true,
+            tags: vec![Tag::new("work").unwrap()],
+            ..Default::default()
+        }.into();
         let common = CommonArgs::from_iter(["random"], false);
         let request = Request::new(common, specific);
 
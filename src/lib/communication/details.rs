@@ -1,16 +1,22 @@
-//! Info on a [`Stopwatch`] passed to the client.
+//! Info on a [`Stopwatch`] or [`Timer`] passed to the client.
 
-use std::time::{SystemTime, Duration};
+use std::{collections::BTreeSet, time::{SystemTime, Duration}};
 
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
 use crate::{
+    fmt::Formatter,
     models::{
-        stopwatch::{State, Stopwatch},
-        lap::FinishedLap
+        auto_lap::AutoLap,
+        stopwatch::{ArchivedRun, State, Stopwatch},
+        lap::FinishedLap,
+        pomodoro::Pomodoro,
+        program::ProgramProgress,
+        run::RunProgress,
+        timer::{Timer, TimerOutcome}
     },
-    identifiers::Identifier
+    identifiers::{Identifier, Tag}
 };
 
 /// Details about a [`Stopwatch`]. See the methods and fields to see what
@@ -18,11 +24,32 @@ use crate::{
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StopwatchDetails {
     pub identifier: Identifier,
+    pub tags: BTreeSet<Tag>,
     pub state: State,
     pub start_time: Option<SystemTime>,
     pub total_time: Duration,
     laps_count: usize,
     current_lap_time: Duration,
+    /// Statistics about the laps that have finished.
+    pub lap_stats: Option<LapStatistics>,
+    /// The run the stopwatch is doing, if any.
+    #[serde(default)]
+    pub run: Option<RunProgress>,
+    /// Progress towards the goal for the total time.
+    #[serde(default)]
+    pub goal: Option<GoalProgress>,
+    /// Progress of the current lap towards the goal for each lap.
+    #[serde(default)]
+    pub lap_goal: Option<GoalProgress>,
+    /// Phase and cycle if the stopwatch is in pomodoro mode.
+    #[serde(default)]
+    pub pomodoro: Option<Pomodoro>,
+    /// Interval program the stopwatch is running, if any.
+    #[serde(default)]
+    pub program: Option<ProgramProgress>,
+    /// Policy the stopwatch starts new laps by, if any.
+    #[serde(default)]
+    pub auto_lap: Option<AutoLap>,
     pub verbose_info: Option<VerboseDetails>
 }
 
@@ -30,11 +57,19 @@ impl StopwatchDetails {
     /// Extract details from a [`Stopwatch`]. Optionally include `verbose` info.
     pub fn from_stopwatch(stopwatch: &Stopwatch, verbose: bool) -> Self {
         let identifier = stopwatch.identifier.clone();
+        let tags = stopwatch.tags.clone();
         let state = stopwatch.state();
         let start_time = stopwatch.start_time();
         let total_time = stopwatch.total_time();
         let laps_count = stopwatch.laps();
         let current_lap_time = stopwatch.last_lap().unwrap().duration;
+        let lap_stats = LapStatistics::from_laps(stopwatch.finished_laps());
+        let run = stopwatch.run.clone();
+        let goal = stopwatch.goals.total.map(|target| GoalProgress::new(target, total_time));
+        let lap_goal = stopwatch.goals.lap.map(|target| GoalProgress::new(target, current_lap_time));
+        let pomodoro = stopwatch.pomodoro;
+        let program = stopwatch.program.clone();
+        let auto_lap = stopwatch.auto_lap;
         let verbose_info = if verbose {
             Some(VerboseDetails::from_stopwatch(stopwatch))
         } else {
@@ -42,17 +77,26 @@ impl StopwatchDetails {
         };
         Self {
             identifier,
+            tags,
             state,
             start_time,
             total_time,
             laps_count,
             current_lap_time,
+            lap_stats,
+            run,
+            goal,
+            lap_goal,
+            pomodoro,
+            program,
+            auto_lap,
             verbose_info
         }
     }
 
     /// Create a dummy set of [`StopwatchDetails`].
     pub fn dummy(identifier: Identifier) -> Self {
+        let tags = BTreeSet::new();
         let state = State::Playing;
         let start_time = Some(SystemTime::UNIX_EPOCH);
         let total_time = SystemTime::now().duration_since(start_time.unwrap()).unwrap();
@@ -60,18 +104,29 @@ impl StopwatchDetails {
             id: Uuid::new_v4(),
             sw_id: identifier.id,
             start: start_time.unwrap(),
-            duration: total_time
+            duration: total_time,
+            segments: vec![],
+            label: None,
+            note: None
         }];
         let laps_count = 1;
         let current_lap_time = total_time;
-        let verbose_info = Some(VerboseDetails { laps });
+        let verbose_info = Some(VerboseDetails { laps, archived_runs: vec![] });
         Self {
             identifier,
+            tags,
             state,
             start_time,
             total_time,
             laps_count,
             current_lap_time,
+            lap_stats: None,
+            run: None,
+            goal: None,
+            lap_goal: None,
+            pomodoro: None,
+            program: None,
+            auto_lap: None,
             verbose_info
         }
     }
@@ -95,6 +150,18 @@ impl StopwatchDetails {
         }
     }
 
+    /// Progress of the current lap through its pomodoro phase.
+    pub fn phase_progress(&self) -> Option<GoalProgress> {
+        self.pomodoro.map(|p| GoalProgress::new(p.phase_length(), self.current_lap_time()))
+    }
+
+    /// Progress of the current lap through its step of the program. [`None`]
+    /// if the program has finished.
+    pub fn step_progress(&self) -> Option<GoalProgress> {
+        let step = self.program.as_ref()?.current_step()?;
+        Some(GoalProgress::new(step.duration, self.current_lap_time()))
+    }
+
     /// Time elapsed for the current lap.
     pub fn current_lap_time(&self) -> Duration {
         match &self.verbose_info {
@@ -107,27 +174,303 @@ impl StopwatchDetails {
     pub fn get_raw_id(&self) -> String {
         self.identifier.to_string()
     }
+
+    /// Split times of each lap, if [`VerboseDetails`] are available.
+    pub fn lap_splits(&self) -> Option<Vec<LapSplit>> {
+        let best = self.lap_stats.as_ref().map(|stats| stats.min);
+        self.verbose_info.as_ref().map(|vi| LapSplit::from_laps(&vi.laps, best))
+    }
+
+    /// Compare each segment of the run to the personal best and golds, if the
+    /// stopwatch is doing a run and [`VerboseDetails`] are available.
+    pub fn run_comparisons(&self) -> Option<Vec<SegmentComparison>> {
+        let run = self.run.as_ref()?;
+        let vi = self.verbose_info.as_ref()?;
+        Some(SegmentComparison::from_laps(run, &vi.laps))
+    }
 }
 
 /// Extra information, supplements [`StopwatchDetails`].
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VerboseDetails {
-    pub laps: Vec<FinishedLap>
+    pub laps: Vec<FinishedLap>,
+    /// Laps from before the stopwatch was reset.
+    #[serde(default)]
+    pub archived_runs: Vec<ArchivedRun>
 }
 
 impl VerboseDetails {
     /// Create [`VerboseDetails`] from a [`Stopwatch`].
     pub fn from_stopwatch(stopwatch: &Stopwatch) -> Self {
         let laps = stopwatch.all_laps();
-        Self { laps }
+        let archived_runs = stopwatch.archived_runs().to_vec();
+        Self { laps, archived_runs }
+    }
+}
+
+/// Statistics about the durations of laps.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LapStatistics {
+    /// Number of laps the statistics were calculated from.
+    pub count: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Population standard deviation.
+    pub std_dev: Duration,
+    /// Lap number of the shortest lap, starting from 1.
+    pub fastest: usize,
+    /// Lap number of the longest lap, starting from 1.
+    pub slowest: usize
+}
+
+impl LapStatistics {
+    /// Calculate statistics for `laps`. Returns [`None`] if there are no laps.
+    /// If more than one lap is the fastest or slowest, the earliest one is
+    /// picked.
+    pub fn from_laps(laps: &[FinishedLap]) -> Option<Self> {
+        let count = laps.len();
+        let durations: Vec<Duration> = laps.iter().map(|lap| lap.duration).collect();
+        // `min_by_key` returns the first minimum but `max_by_key` returns the
+        // last maximum, so compare in reverse for the slowest lap
+        let (fastest, min) = durations.iter().copied().enumerate()
+            .min_by_key(|(_, duration)| *duration)?;
+        let (slowest, max) = durations.iter().copied().enumerate().rev()
+            .max_by_key(|(_, duration)| *duration)?;
+        let mean = durations.iter()
+            .fold(Duration::ZERO, |total, duration| total.saturating_add(*duration))
+            / count as u32;
+        let mut sorted = durations.clone();
+        sorted.sort();
+        let median = if count % 2 == 1 {
+            sorted[count / 2]
+        } else {
+            // Halfway between the middle two without adding them up
+            sorted[count / 2 - 1] + (sorted[count / 2] - sorted[count / 2 - 1]) / 2
+        };
+        let variance = durations.iter()
+            .map(|duration| (duration.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>() / count as f64;
+        let std_dev = Duration::from_secs_f64(variance.sqrt());
+        Some(Self {
+            count,
+            min,
+            max,
+            mean,
+            median,
+            std_dev,
+            fastest: fastest + 1,
+            slowest: slowest + 1
+        })
+    }
+}
+
+/// How much longer or shorter a lap was than another lap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LapDelta {
+    Slower(Duration),
+    Faster(Duration),
+    Even
+}
+
+impl LapDelta {
+    /// Compare the duration of a lap to the duration of another lap.
+    pub fn between(lap: Duration, other: Duration) -> Self {
+        if lap > other {
+            Self::Slower(lap - other)
+        } else if lap < other {
+            Self::Faster(other - lap)
+        } else {
+            Self::Even
+        }
+    }
+
+    /// Format this delta with `+` in front if the lap was slower, and `-` if
+    /// it was faster.
+    pub fn format(&self, formatter: &Formatter) -> String {
+        match self {
+            Self::Slower(delta) => format!("+{}", formatter.format_duration(*delta)),
+            Self::Faster(delta) => format!("-{}", formatter.format_duration(*delta)),
+            Self::Even => formatter.format_duration(Duration::ZERO)
+        }
+    }
+}
+
+/// How far along a stopwatch or lap is towards its goal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalProgress {
+    /// How long it should take.
+    pub target: Duration,
+    /// How long it has taken so far.
+    pub elapsed: Duration
+}
+
+impl GoalProgress {
+    pub fn new(target: Duration, elapsed: Duration) -> Self {
+        Self { target, elapsed }
+    }
+
+    /// Whether the time has crossed the goal.
+    pub fn reached(&self) -> bool {
+        self.elapsed >= self.target
+    }
+
+    /// Time left before the goal is reached.
+    pub fn remaining(&self) -> Duration {
+        self.target.saturating_sub(self.elapsed)
+    }
+
+    /// Time spent after the goal was reached.
+    pub fn overtime(&self) -> Duration {
+        self.elapsed.saturating_sub(self.target)
+    }
+
+    /// How much of the goal has been used up, where 1.0 means the goal has
+    /// just been reached. Can be more than 1.0.
+    pub fn fraction(&self) -> f64 {
+        if self.target.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f64() / self.target.as_secs_f64()
+        }
+    }
+
+    /// Format as "x left" or "x over".
+    pub fn format(&self, formatter: &Formatter) -> String {
+        if self.elapsed > self.target {
+            format!("{} over", formatter.format_duration(self.overtime()))
+        } else {
+            format!("{} left", formatter.format_duration(self.remaining()))
+        }
+    }
+}
+
+/// Split time of a lap, like the ones shown in racing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LapSplit {
+    /// Time since the start of the stopwatch up to the end of this lap.
+    pub split: Duration,
+    /// Compared to the previous lap. [`None`] for the first lap.
+    pub to_previous: Option<LapDelta>,
+    /// Compared to the best lap. [`None`] if there is no best lap.
+    pub to_best: Option<LapDelta>
+}
+
+impl LapSplit {
+    /// Calculate the split times for `laps`, comparing them to the duration
+    /// of the `best` lap.
+    pub fn from_laps(laps: &[FinishedLap], best: Option<Duration>) -> Vec<Self> {
+        let mut split = Duration::ZERO;
+        let mut previous: Option<Duration> = None;
+        laps.iter()
+            .map(|lap| {
+                split = split.saturating_add(lap.duration);
+                let to_previous = previous.map(|p| LapDelta::between(lap.duration, p));
+                let to_best = best.map(|b| LapDelta::between(lap.duration, b));
+                previous = Some(lap.duration);
+                Self { split, to_previous, to_best }
+            })
+            .collect()
+    }
+}
+
+/// How a segment of a run compares to the personal best and gold.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentComparison {
+    pub name: String,
+    /// How long the segment took, or [`None`] if it hasn't been reached yet.
+    pub time: Option<Duration>,
+    /// Time since the start of the run up to the end of this segment.
+    pub split: Option<Duration>,
+    /// Compared to the split of the personal best at this segment.
+    pub to_personal_best: Option<LapDelta>,
+    /// Compared to the best time for this segment.
+    pub to_gold: Option<LapDelta>
+}
+
+impl SegmentComparison {
+    /// Compare `laps` to the segments of `run`. The lap that is still going
+    /// is compared as well.
+    pub fn from_laps(run: &RunProgress, laps: &[FinishedLap]) -> Vec<Self> {
+        let mut split = Duration::ZERO;
+        let mut pb_split = Some(Duration::ZERO);
+        run.segments.iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let time = laps.get(i).map(|lap| lap.duration);
+                pb_split = pb_split.zip(segment.personal_best).map(|(s, pb)| s + pb);
+                let split = time.map(|time| {
+                    split += time;
+                    split
+                });
+                let to_personal_best = split.zip(pb_split)
+                    .map(|(split, pb)| LapDelta::between(split, pb));
+                let to_gold = time.zip(segment.gold)
+                    .map(|(time, gold)| LapDelta::between(time, gold));
+                Self { name: segment.name.clone(), time, split, to_personal_best, to_gold }
+            })
+            .collect()
+    }
+}
+
+/// Details about a [`Timer`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerDetails {
+    pub identifier: Identifier,
+    pub state: State,
+    pub outcome: Option<TimerOutcome>,
+    pub start_time: SystemTime,
+    pub ended_at: Option<SystemTime>,
+    pub target: Duration,
+    pub elapsed: Duration,
+    pub remaining: Duration
+}
+
+impl TimerDetails {
+    /// Extract details from a [`Timer`].
+    pub fn from_timer(timer: &Timer) -> Self {
+        Self {
+            identifier: timer.identifier.clone(),
+            state: timer.state(),
+            outcome: timer.outcome(),
+            start_time: timer.start_time(),
+            ended_at: timer.ended_at(),
+            target: timer.target,
+            elapsed: timer.elapsed(),
+            remaining: timer.remaining()
+        }
+    }
+
+    /// Get a string that this timer can be identified by.
+    pub fn get_raw_id(&self) -> String {
+        self.identifier.to_string()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{models::stopwatch::Stopwatch, identifiers::Name};
+    use std::time::{Duration, SystemTime};
 
-    use super::StopwatchDetails;
+    use uuid::Uuid;
+
+    use crate::{
+        models::{
+            lap::FinishedLap,
+            run::{Run, RunAttempt, RunProgress},
+            stopwatch::Stopwatch
+        },
+        identifiers::{Name, UniqueId}
+    };
+
+    use super::{
+        GoalProgress,
+        LapDelta,
+        LapSplit,
+        LapStatistics,
+        SegmentComparison,
+        StopwatchDetails
+    };
 
     fn make_stopwatch() -> Stopwatch {
         let mut stopwatch = Stopwatch::start(Name::fixed("aaa"));
@@ -136,6 +479,19 @@ mod test {
         stopwatch
     }
 
+    /// A lap that lasted `secs` seconds.
+    fn lap(secs: u64) -> FinishedLap {
+        FinishedLap {
+            id: Uuid::new_v4(),
+            sw_id: UniqueId::generate(),
+            start: SystemTime::now(),
+            duration: Duration::from_secs(secs),
+            segments: vec![],
+            label: None,
+            note: None
+        }
+    }
+
     fn basic_asserts(stopwatch: &Stopwatch, info: &StopwatchDetails) {
         assert_eq!(stopwatch.identifier, info.identifier);
         assert_eq!(stopwatch.state(), info.state);
@@ -152,6 +508,80 @@ mod test {
         assert_eq!(info.verbose_info, None);
     }
 
+    #[test]
+    fn test_lap_statistics() {
+        assert_eq!(LapStatistics::from_laps(&[]), None);
+
+        let laps = [lap(4), lap(2), lap(8), lap(2), lap(4), lap(8)];
+        let stats = LapStatistics::from_laps(&laps).unwrap();
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.min, Duration::from_secs(2));
+        assert_eq!(stats.max, Duration::from_secs(8));
+        assert_eq!(stats.fastest, 2);
+        assert_eq!(stats.slowest, 3);
+        assert_eq!(stats.mean, Duration::from_secs(28) / 6);
+        assert_eq!(stats.median, Duration::from_secs(4));
+        assert!(stats.std_dev.abs_diff(Duration::from_secs_f64(2.494)) < Duration::from_millis(1));
+
+        // Laps that were made longer than the total can hold
+        let huge = [lap(u64::MAX - 1), lap(u64::MAX)];
+        let stats = LapStatistics::from_laps(&huge).unwrap();
+        assert_eq!(stats.median, Duration::new(u64::MAX - 1, 500_000_000));
+    }
+
+    #[test]
+    fn test_lap_splits() {
+        let secs = Duration::from_secs;
+        let laps = [lap(10), lap(7), lap(9)];
+        let splits = LapSplit::from_laps(&laps, Some(secs(7)));
+        assert_eq!(splits.iter().map(|s| s.split).collect::<Vec<_>>(), [secs(10), secs(17), secs(26)]);
+        assert_eq!(splits[0].to_previous, None);
+        assert_eq!(splits[1].to_previous, Some(LapDelta::Faster(secs(3))));
+        assert_eq!(splits[2].to_previous, Some(LapDelta::Slower(secs(2))));
+        assert_eq!(splits[1].to_best, Some(LapDelta::Even));
+        assert_eq!(LapSplit::from_laps(&laps, None)[0].to_best, None);
+    }
+
+    #[test]
+    fn test_segment_comparisons() {
+        let secs = Duration::from_secs;
+        let mut run = Run::new("any%", ["first", "second", "third"]);
+        run.record(RunAttempt { start: None, segments: vec![secs(10), secs(20), secs(30)] });
+        run.record(RunAttempt { start: None, segments: vec![secs(15), secs(15)] });
+        let run = RunProgress::from(&run);
+
+        let comparisons = SegmentComparison::from_laps(&run, &[lap(12), lap(16)]);
+        assert_eq!(comparisons.len(), 3);
+        assert_eq!(comparisons[0].to_personal_best, Some(LapDelta::Slower(secs(2))));
+        assert_eq!(comparisons[0].to_gold, Some(LapDelta::Slower(secs(2))));
+        assert_eq!(comparisons[1].split, Some(secs(28)));
+        assert_eq!(comparisons[1].to_personal_best, Some(LapDelta::Faster(secs(2))));
+        assert_eq!(comparisons[1].to_gold, Some(LapDelta::Slower(secs(1))));
+        assert_eq!(comparisons[2].time, None);
+        assert_eq!(comparisons[2].to_personal_best, None);
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let secs = Duration::from_secs;
+        let progress = GoalProgress::new(secs(30 * 60), secs(18 * 60));
+        assert!(!progress.reached());
+        assert_eq!(progress.remaining(), secs(12 * 60));
+        assert_eq!(progress.overtime(), Duration::ZERO);
+        assert_eq!(progress.fraction(), 0.6);
+
+        let progress = GoalProgress::new(secs(30 * 60), secs(42 * 60));
+        assert!(progress.reached());
+        assert_eq!(progress.overtime(), secs(12 * 60));
+        assert_eq!(progress.remaining(), Duration::ZERO);
+
+        let mut stopwatch = make_stopwatch();
+        stopwatch.goals.total = Some(secs(60));
+        let info = StopwatchDetails::from_stopwatch(&stopwatch, false);
+        assert_eq!(info.goal.map(|g| g.target), Some(secs(60)));
+        assert_eq!(info.lap_goal, None);
+    }
+
     #[test]
     fn test_from_stopwatch_verbose() {
         let stopwatch = make_stopwatch();
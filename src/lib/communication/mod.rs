@@ -23,6 +23,20 @@ pub fn args_to_default_ans(args: &SpecificArgs) -> SpecificAnswer {
         A::Pause(_) => B::Pause(Default::default()),
         A::Lap(_) => B::Lap(Default::default()),
         A::Delete(_) => B::Delete(Default::default()),
+        A::Timer(_) => B::Timer(Default::default()),
+        A::EditLap(_) => B::EditLap(Default::default()),
+        A::Tag(_) => B::Tag(Default::default()),
+        A::Rename(_) => B::Rename(Default::default()),
+        A::Reset(_) => B::Reset(Default::default()),
+        A::Reopen(_) => B::Reopen(Default::default()),
+        A::AdjustLap(_) => B::AdjustLap(Default::default()),
+        A::AddLap(_) => B::AddLap(Default::default()),
+        A::ModifyLap(_) => B::ModifyLap(Default::default()),
+        A::Run(_) => B::Run(Default::default()),
+        A::Import(_) => B::Import(Default::default()),
+        A::Goal(_) => B::Goal(Default::default()),
+        A::Pomodoro(_) => B::Pomodoro(Default::default()),
+        A::Program(_) => B::Program(Default::default()),
     }
 }
 
@@ -39,5 +53,19 @@ pub fn ans_to_default_args(ans: &SpecificAnswer) -> SpecificArgs {
         A::Pause(_) => B::Pause(Default::default()),
         A::Lap(_) => B::Lap(Default::default()),
         A::Delete(_) => B::Delete(Default::default()),
+        A::Timer(_) => B::Timer(Default::default()),
+        A::EditLap(_) => B::EditLap(Default::default()),
+        A::Tag(_) => B::Tag(Default::default()),
+        A::Rename(_) => B::Rename(Default::default()),
+        A::Reset(_) => B::Reset(Default::default()),
+        A::Reopen(_) => B::Reopen(Default::default()),
+        A::AdjustLap(_) => B::AdjustLap(Default::default()),
+        A::AddLap(_) => B::AddLap(Default::default()),
+        A::ModifyLap(_) => B::ModifyLap(Default::default()),
+        A::Run(_) => B::Run(Default::default()),
+        A::Import(_) => B::Import(Default::default()),
+        A::Goal(_) => B::Goal(Default::default()),
+        A::Pomodoro(_) => B::Pomodoro(Default::default()),
+        A::Program(_) => B::Program(Default::default()),
     }
 }
\ No newline at end of file
@@ -4,7 +4,11 @@
 
 use serde::{Serialize, Deserialize};
 
-use crate::impl_into_enum_variant;
+use crate::{impl_into_enum_variant, models::run::Run};
+#[allow(unused)]
+use crate::models::{stopwatch::Stopwatch, timer::Timer}; // for see also documentation
+
+use super::details::TimerDetails;
 
 /// Type of action `swd` took and the result of that action.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -15,7 +19,21 @@ pub enum SpecificAnswer {
     Play(PlayAnswer),
     Pause(PauseAnswer),
     Lap(LapAnswer),
-    Delete(DeleteAnswer)
+    Delete(DeleteAnswer),
+    Timer(TimerAnswer),
+    EditLap(EditLapAnswer),
+    Tag(TagAnswer),
+    Rename(RenameAnswer),
+    Reset(ResetAnswer),
+    Reopen(ReopenAnswer),
+    AdjustLap(AdjustLapAnswer),
+    AddLap(AddLapAnswer),
+    ModifyLap(ModifyLapAnswer),
+    Run(RunAnswer),
+    Import(ImportAnswer),
+    Goal(GoalAnswer),
+    Pomodoro(PomodoroAnswer),
+    Program(ProgramAnswer)
 }
 
 /// Kind of information coming from `swd`.
@@ -60,6 +78,77 @@ pub struct LapAnswer;
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeleteAnswer;
 
+/// Reply from `swd` after editing a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditLapAnswer;
+
+/// Reply from `swd` after adding or taking away time from a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdjustLapAnswer;
+
+/// Reply from `swd` after adding a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddLapAnswer;
+
+/// Reply from `swd` after deleting, merging, splitting or changing the
+/// duration of a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifyLapAnswer;
+
+/// Reply from `swd` after changing the tags of [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagAnswer;
+
+/// Reply from `swd` after renaming a [`Stopwatch`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameAnswer;
+
+/// Reply from `swd` after resetting [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResetAnswer;
+
+/// Reply from `swd` after reopening [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReopenAnswer;
+
+/// Reply from `swd` after doing something with [`Timer`]s.
+///
+/// Since [`Reply::successful`](super::server::Reply::successful) only holds
+/// stopwatches, details about the timers are returned here instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerAnswer {
+    /// Timers that were successfully acted upon, in the order requested by the
+    /// client or in access order if no timers were requested.
+    pub timers: Vec<(String, TimerDetails)>
+}
+
+/// Reply from `swd` after changing the goals of [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalAnswer;
+
+/// Reply from `swd` after skipping a phase or stopping [`Stopwatch`]es in
+/// pomodoro mode.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PomodoroAnswer;
+
+/// Reply from `swd` after skipping a step or aborting [`Stopwatch`]es running
+/// an interval program.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramAnswer;
+
+/// Reply from `swd` after importing [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportAnswer;
+
+/// Reply from `swd` after doing something with [`Run`]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunAnswer {
+    /// Runs that were successfully acted upon, in the order requested by the
+    /// client or sorted by name if no runs were requested. Deleted runs are
+    /// returned as they were before being deleted.
+    pub runs: Vec<Run>
+}
+
 impl_into_enum_variant!(SpecificAnswer {
     Info(InfoAnswer),
     Start(StartAnswer),
@@ -67,5 +156,19 @@ impl_into_enum_variant!(SpecificAnswer {
     Play(PlayAnswer),
     Pause(PauseAnswer),
     Lap(LapAnswer),
-    Delete(DeleteAnswer)
+    Delete(DeleteAnswer),
+    Timer(TimerAnswer),
+    EditLap(EditLapAnswer),
+    Tag(TagAnswer),
+    Rename(RenameAnswer),
+    Reset(ResetAnswer),
+    Reopen(ReopenAnswer),
+    AdjustLap(AdjustLapAnswer),
+    AddLap(AddLapAnswer),
+    ModifyLap(ModifyLapAnswer),
+    Run(RunAnswer),
+    Import(ImportAnswer),
+    Goal(GoalAnswer),
+    Pomodoro(PomodoroAnswer),
+    Program(ProgramAnswer)
 });
\ No newline at end of file
@@ -1,8 +1,23 @@
 //! Specific request types and arguments for them.
 
+use std::time::{Duration, SystemTime};
+
+use clap::ValueEnum;
 use serde::{Serialize, Deserialize};
 
-use crate::impl_into_enum_variant;
+use crate::{
+    identifiers::{LapRef, Tag},
+    impl_into_enum_variant,
+    models::{
+        auto_lap::AutoLap,
+        lap::{LapAnnotation, TimeAdjustment},
+        pomodoro::PomodoroConfig,
+        program::Program,
+        stopwatch::Goals
+    }
+};
+#[allow(unused)]
+use crate::models::{run::Run, stopwatch::Stopwatch, timer::Timer}; // for see also documentation
 
 /// Possible actions `swd` can take and the extra arguments the action needs.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,39 +28,292 @@ pub enum SpecificArgs {
     Play(PlayArgs),
     Pause(PauseArgs),
     Lap(LapArgs),
-    Delete(DeleteArgs)
+    Delete(DeleteArgs),
+    Timer(TimerArgs),
+    EditLap(EditLapArgs),
+    Tag(TagArgs),
+    Rename(RenameArgs),
+    Reset(ResetArgs),
+    Reopen(ReopenArgs),
+    AdjustLap(AdjustLapArgs),
+    AddLap(AddLapArgs),
+    ModifyLap(ModifyLapArgs),
+    Run(RunArgs),
+    Import(ImportArgs),
+    Goal(GoalArgs),
+    Pomodoro(PomodoroArgs),
+    Program(ProgramArgs)
 }
 
 /// Request for information about stopwatches managed by `swd`.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InfoArgs;
 
-/// Get `swd` to create a new [`Stopwatch`].
+/// Get `swd` to create a new [`Stopwatch`] for each name given.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StartArgs {
-    pub fix_bad_names: bool
+    pub fix_bad_names: bool,
+    /// Don't create any of the stopwatches if one of the names is bad or
+    /// already taken.
+    pub all_or_nothing: bool,
+    /// What to do if a stopwatch with the same name already exists.
+    pub if_exists: IfExists,
+    /// Create the stopwatch without playing it. Ignored if `at` is given.
+    pub paused: bool,
+    /// Tags for the new stopwatch.
+    pub tags: Vec<Tag>,
+    /// When the stopwatch actually started, if not now.
+    pub at: Option<SystemTime>,
+    /// Name of the run the stopwatch is doing, if any.
+    pub run: Option<String>,
+    pub goals: Goals,
+    /// Start the stopwatch in pomodoro mode with these phase lengths.
+    pub pomodoro: Option<PomodoroConfig>,
+    /// Interval program for the stopwatch to run, if any.
+    pub program: Option<Program>,
+    /// Start new laps by themselves according to this policy.
+    pub auto_lap: Option<AutoLap>
+}
+
+/// What [`StartArgs`] does when a [`Stopwatch`] with the same name already
+/// exists.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum IfExists {
+    /// Report the name as taken.
+    #[default]
+    Error,
+    /// Return the existing stopwatch untouched.
+    Reuse,
+    /// Play the existing stopwatch, or reopen it if it has ended.
+    Resume,
+    /// End and delete the existing stopwatch and start a new one in its place.
+    Restart,
+    /// Start a new stopwatch with `-2`, `-3` and so on after the name.
+    Suffix
 }
 
 /// Stop a [`Stopwatch`].
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct StopArgs;
+pub struct StopArgs {
+    /// When the stopwatch actually stopped, if not now.
+    pub at: Option<SystemTime>
+}
 
 /// Request to play a [`Stopwatch`].
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct PlayArgs;
+pub struct PlayArgs {
+    /// When the stopwatch actually continued, if not now.
+    pub at: Option<SystemTime>
+}
 
 /// Request to pause a [`Stopwatch`].
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct PauseArgs;
+pub struct PauseArgs {
+    /// When the stopwatch was actually paused, if not now.
+    pub at: Option<SystemTime>
+}
 
 /// Request to create a new lap.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct LapArgs;
+pub struct LapArgs {
+    /// Label and note for the lap that is being ended.
+    pub annotation: LapAnnotation,
+    /// When the new lap actually started, if not now.
+    pub at: Option<SystemTime>,
+    /// Create the new lap without playing it. Ignored if `at` is given.
+    pub paused: bool
+}
 
 /// Delete action.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeleteArgs;
 
+/// Change the label or note of a lap after the fact.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditLapArgs {
+    /// Which lap to edit. Laps are numbered from 1, including the current lap.
+    pub lap: LapRef,
+    pub annotation: LapAnnotation
+}
+
+/// Add or take away time from a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdjustLapArgs {
+    /// Which lap to adjust. Laps are numbered from 1, including the current
+    /// lap.
+    pub lap: LapRef,
+    pub adjustment: TimeAdjustment
+}
+
+/// Add a lap that was timed somewhere else to a [`Stopwatch`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddLapArgs {
+    /// When the lap started.
+    pub start: SystemTime,
+    /// How long the lap lasted.
+    pub duration: Duration,
+    pub annotation: LapAnnotation
+}
+
+impl Default for AddLapArgs {
+    fn default() -> Self {
+        Self {
+            start: SystemTime::UNIX_EPOCH,
+            duration: Duration::ZERO,
+            annotation: LapAnnotation::default()
+        }
+    }
+}
+
+/// Delete, merge, split or change the duration of a lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifyLapArgs {
+    pub lap: LapRef,
+    pub action: LapAction
+}
+
+/// What to do with the lap in [`ModifyLapArgs`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LapAction {
+    /// Remove a lap that has finished.
+    #[default] Delete,
+    /// Merge the lap with the lap after it.
+    Merge,
+    /// Split a lap that has finished after it has been playing for `offset`.
+    Split { offset: Duration },
+    /// Change how long the lap has been playing for.
+    SetDuration { duration: Duration }
+}
+
+/// Add or remove tags from [`Stopwatch`]es.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagArgs {
+    pub add: Vec<Tag>,
+    pub remove: Vec<Tag>
+}
+
+/// Give a [`Stopwatch`] a new name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameArgs {
+    pub name: String,
+    pub fix_bad_names: bool
+}
+
+/// Throw away the laps of [`Stopwatch`]es and start them again.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResetArgs {
+    /// Keep the old laps as an archived run.
+    pub archive: bool,
+    /// Don't start the new lap immediately.
+    pub paused: bool
+}
+
+/// Let [`Stopwatch`]es that have ended continue from a new lap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReopenArgs {
+    /// Don't start the new lap immediately.
+    pub paused: bool
+}
+
+/// Replace the goals of [`Stopwatch`]es. Empty [`Goals`] remove them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalArgs {
+    pub goals: Goals
+}
+
+/// Control [`Stopwatch`]es in pomodoro mode. New ones are made with
+/// [`StartArgs::pomodoro`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PomodoroArgs {
+    pub action: PomodoroAction
+}
+
+/// What to do with the [`Stopwatch`]es in [`PomodoroArgs`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PomodoroAction {
+    /// End the current phase early and start the next one.
+    #[default] Skip,
+    /// End the stopwatch in the middle of its current phase.
+    Stop
+}
+
+/// Control [`Stopwatch`]es running an interval [`Program`]. New ones are made
+/// with [`StartArgs::program`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramArgs {
+    pub action: ProgramAction
+}
+
+/// What to do with the [`Stopwatch`]es in [`ProgramArgs`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgramAction {
+    /// End the current step early and start the next one.
+    #[default] Skip,
+    /// End the stopwatch before the program is completed.
+    Abort
+}
+
+/// Request to do something with [`Timer`]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerArgs {
+    pub action: TimerAction
+}
+
+/// What to do with the [`Timer`]s in the request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimerAction {
+    /// Get information about timers. If no timers are specified, all timers
+    /// are returned.
+    #[default] Info,
+    /// Create a new timer that counts down from `target` for each name.
+    Start { target: Duration, fix_bad_names: bool },
+    Play,
+    Pause,
+    /// End timers before they expire.
+    Cancel,
+    Delete
+}
+
+/// Create [`Stopwatch`]es that have already ended from laps that were timed
+/// somewhere else. The names of the stopwatches go in
+/// [`CommonArgs::raw_identifiers`](super::client::CommonArgs::raw_identifiers),
+/// one for each stopwatch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportArgs {
+    pub fix_bad_names: bool,
+    /// Tags for every new stopwatch.
+    pub tags: Vec<Tag>,
+    pub stopwatches: Vec<ImportedStopwatch>
+}
+
+/// Laps of a [`Stopwatch`] in [`ImportArgs`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedStopwatch {
+    /// When the first lap started.
+    pub start: SystemTime,
+    /// Duration of each lap, in order.
+    pub laps: Vec<(Duration, LapAnnotation)>
+}
+
+/// Request to do something with [`Run`]s. The names of the runs go in
+/// [`CommonArgs::raw_identifiers`](super::client::CommonArgs::raw_identifiers).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunArgs {
+    pub action: RunAction
+}
+
+/// What to do with the [`Run`]s in the request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunAction {
+    /// Get the segments, personal best and golds of runs. If no runs are
+    /// specified, all runs are returned.
+    #[default] Info,
+    /// Create a run with these segments. An existing run is only replaced if
+    /// `replace` is set, and keeps its history if the segments stay the same.
+    Define { segments: Vec<String>, replace: bool },
+    Delete
+}
+
 impl_into_enum_variant!(SpecificArgs {
     Info(InfoArgs),
     Start(StartArgs),
@@ -53,5 +321,19 @@ impl_into_enum_variant!(SpecificArgs {
     Play(PlayArgs),
     Pause(PauseArgs),
     Lap(LapArgs),
-    Delete(DeleteArgs)
+    Delete(DeleteArgs),
+    Timer(TimerArgs),
+    EditLap(EditLapArgs),
+    Tag(TagArgs),
+    Rename(RenameArgs),
+    Reset(ResetArgs),
+    Reopen(ReopenArgs),
+    AdjustLap(AdjustLapArgs),
+    AddLap(AddLapArgs),
+    ModifyLap(ModifyLapArgs),
+    Run(RunArgs),
+    Import(ImportArgs),
+    Goal(GoalArgs),
+    Pomodoro(PomodoroArgs),
+    Program(ProgramArgs)
 });
\ No newline at end of file
@@ -4,7 +4,7 @@ use std::{fmt, collections::HashMap, hash::Hash};
 
 use serde::{Serialize, Deserialize};
 
-use crate::error::{FindStopwatchError, InvalidState, BadNameError};
+use crate::error::{FindStopwatchError, FindTimerError, InvalidState, InvalidLap, BadNameError};
 
 use super::{details::StopwatchDetails, reply_specifics::SpecificAnswer};
 
@@ -26,7 +26,9 @@ where
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ServerError {
     FindStopwatchError(FindStopwatchError),
+    FindTimerError(FindTimerError),
     InvalidState(InvalidState),
+    InvalidLap(InvalidLap),
     BadName(BadNameError),
     Other(String)
 }
@@ -37,7 +39,9 @@ impl ServerError {
         use ServerError::*;
         match self {
             FindStopwatchError(fse) => Some(&fse.raw_identifier),
+            FindTimerError(fte) => Some(&fte.raw_identifier),
             InvalidState(is) => Some(&is.raw_identifier),
+            InvalidLap(il) => Some(&il.raw_identifier),
             BadName(_) => None, // TODO: Should have identifier
             Other(_) => None
         }
@@ -49,7 +53,9 @@ impl fmt::Display for ServerError {
         use ServerError::*;
         match self {
             FindStopwatchError(fse) => write!(f, "{}", fse.diagnose()),
+            FindTimerError(fte) => write!(f, "{}", fte),
             InvalidState(is) => write!(f, "{}", is),
+            InvalidLap(il) => write!(f, "{}", il),
             BadName(bne) => bne.fmt(f),
             Other(s) => write!(f, "{}", s)
         }
@@ -64,12 +70,24 @@ impl From<FindStopwatchError> for ServerError {
     }
 }
 
+impl From<FindTimerError> for ServerError {
+    fn from(fte: FindTimerError) -> Self {
+        Self::FindTimerError(fte)
+    }
+}
+
 impl From<InvalidState> for ServerError {
     fn from(is: InvalidState) -> Self {
         Self::InvalidState(is)
     }
 }
 
+impl From<InvalidLap> for ServerError {
+    fn from(il: InvalidLap) -> Self {
+        Self::InvalidLap(il)
+    }
+}
+
 impl From<String> for ServerError {
     fn from(error: String) -> Self {
         Self::Other(error)
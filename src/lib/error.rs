@@ -1,12 +1,14 @@
 //! Custom errors for Stopwatchd.
 
-use std::fmt;
+use std::{fmt, time::{Duration, SystemTime}};
 
+use chrono::{DateTime, Local};
 use serde::{Serialize, Deserialize};
 
 use crate::{
-    identifiers::Identifier,
-    models::stopwatch::State
+    fmt::{DEFAULT_DATETIME_FORMAT, Formatter},
+    identifiers::{Identifier, UniqueId},
+    models::{lap::TimeAdjustment, stopwatch::State}
 };
 #[allow(unused)]
 use crate::models::stopwatch::Stopwatch;
@@ -58,6 +60,25 @@ impl fmt::Display for FindStopwatchError {
 
 impl std::error::Error for FindStopwatchError { }
 
+/// No matches or multiple conflicting matches for a raw identifier of a
+/// [`Timer`](crate::models::timer::Timer).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FindTimerError {
+    pub raw_identifier: String,
+    pub duplicates: Vec<Identifier>
+}
+
+impl fmt::Display for FindTimerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.duplicates.len() {
+            0 => write!(f, "no timer was found with identifier: {}", self.raw_identifier),
+            n => write!(f, "{} timers were found with identifier: {}", n, self.raw_identifier)
+        }
+    }
+}
+
+impl std::error::Error for FindTimerError { }
+
 /// Action does not apply for a [`Stopwatch`] because it's in the wrong
 /// [`State`].
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -74,14 +95,256 @@ impl fmt::Display for InvalidState {
 
 impl std::error::Error for InvalidState { }
 
-/// If a name starts with '@'.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct BadNameError;
+/// A lap of a [`Stopwatch`] could not be changed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LapError {
+    /// There is no lap numbered `lap`. Laps are numbered from 1 and include
+    /// the current lap.
+    NoSuchLap { lap: usize, laps_count: usize },
+    /// An action cannot be backdated to a time in the future.
+    FutureTime,
+    /// An action cannot be backdated to before the lap was last played or
+    /// paused.
+    TooEarly { earliest: SystemTime },
+    /// Lap `lap` cannot be adjusted because its duration would become
+    /// negative.
+    BadAdjustment { lap: usize, adjustment: TimeAdjustment },
+    /// A lap being added overlaps with lap `lap`.
+    Overlap { lap: usize },
+    /// No lap has this id.
+    UnknownLap { id: UniqueId },
+    /// Lap `lap` is the current lap, which can't be changed this way until it
+    /// has finished.
+    Unfinished { lap: usize },
+    /// A stopwatch must have at least one lap.
+    OnlyLap,
+    /// Lap `lap` is the last lap, so there's no lap after it to merge with.
+    NothingToMerge { lap: usize },
+    /// Lap `lap` is not longer than `offset`, so it can't be split there.
+    BadSplit { lap: usize, offset: Duration }
+}
+
+impl fmt::Display for LapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use LapError::*;
+        match self {
+            NoSuchLap { lap, laps_count } => write!(
+                f,
+                "there is no lap {} (there are {} laps)",
+                lap,
+                laps_count
+            ),
+            FutureTime => write!(f, "time is in the future"),
+            TooEarly { earliest } => write!(
+                f,
+                "time cannot be earlier than {}",
+                DateTime::<Local>::from(*earliest).format(DEFAULT_DATETIME_FORMAT)
+            ),
+            BadAdjustment { lap, adjustment } => {
+                let formatter = Formatter::default();
+                match adjustment {
+                    TimeAdjustment::Add(amount) => write!(
+                        f,
+                        "adding {} to lap {} makes it too long",
+                        formatter.format_duration(*amount),
+                        lap
+                    ),
+                    TimeAdjustment::Subtract(amount) => write!(
+                        f,
+                        "lap {} is shorter than {}",
+                        lap,
+                        formatter.format_duration(*amount)
+                    )
+                }
+            },
+            Overlap { lap } => write!(f, "new lap overlaps with lap {}", lap),
+            UnknownLap { id } => write!(f, "there is no lap with id {}", id),
+            Unfinished { lap } => write!(f, "lap {} has not finished yet", lap),
+            OnlyLap => write!(f, "cannot delete the only lap"),
+            NothingToMerge { lap } => write!(f, "there is no lap after lap {} to merge with", lap),
+            BadSplit { lap, offset } => write!(
+                f,
+                "lap {} is not longer than {}",
+                lap,
+                Formatter::default().format_duration(*offset)
+            )
+        }
+    }
+}
+
+impl std::error::Error for LapError { }
+
+/// [`LapError`] for a particular [`Stopwatch`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidLap {
+    pub raw_identifier: String,
+    pub error: LapError
+}
+
+impl fmt::Display for InvalidLap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.raw_identifier, self.error)
+    }
+}
+
+impl std::error::Error for InvalidLap { }
+
+/// If a tag is empty, has whitespace or commas, or starts with '+' or '@'.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BadTagError {
+    pub tag: String
+}
+
+impl fmt::Display for BadTagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid tag: {:?}", self.tag)
+    }
+}
+
+impl std::error::Error for BadTagError { }
+
+/// If a lap is neither a lap number nor a lap id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BadLapRefError {
+    pub lap_ref: String
+}
+
+impl fmt::Display for BadLapRefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid lap number or lap id: {:?}", self.lap_ref)
+    }
+}
+
+impl std::error::Error for BadLapRefError { }
+
+/// A name was rejected by a [`NamePolicy`](crate::identifiers::NamePolicy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BadNameError {
+    /// Name starts with '@' or '+', so it would be read as an id or a tag.
+    Reserved,
+    /// Name has a character that the policy doesn't allow.
+    BadChar(char),
+    /// Name has more characters than the policy allows.
+    TooLong { max_length: usize }
+}
 
 impl fmt::Display for BadNameError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "BadNameError")
+        use BadNameError::*;
+        match self {
+            Reserved => write!(f, "name cannot start with '@' or '+'"),
+            BadChar(c) => write!(f, "name cannot contain {:?}", c),
+            TooLong { max_length } => {
+                write!(f, "name cannot be longer than {} characters", max_length)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BadNameError { }
+
+/// A [`StopwatchSnapshot`](crate::models::snapshot::StopwatchSnapshot) could
+/// not be read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotError {
+    /// Snapshot was made by a newer version of Stopwatchd.
+    UnsupportedVersion(u32),
+    /// Snapshot does not have the expected structure.
+    Malformed(String)
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(v) => write!(f, "unsupported snapshot schema version: {}", v),
+            Self::Malformed(e) => write!(f, "malformed snapshot: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError { }
+
+/// A duration given by the user could not be understood.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseDurationError {
+    pub input: String
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid duration: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseDurationError { }
+
+/// A time given by the user could not be understood.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseTimeError {
+    pub input: String
+}
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid time: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseTimeError { }
+
+/// An auto-lap policy given by the user could not be understood.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseAutoLapError {
+    pub input: String
+}
+
+impl fmt::Display for ParseAutoLapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid auto-lap policy: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseAutoLapError { }
+
+/// A LiveSplit splits file could not be read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LssError {
+    /// File is not valid XML.
+    Xml(String),
+    /// File is valid XML but not a valid splits file.
+    Malformed(String)
+}
+
+impl fmt::Display for LssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xml(e) => write!(f, "invalid xml: {}", e),
+            Self::Malformed(e) => write!(f, "malformed splits file: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for LssError { }
+
+/// An interval program could not be read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgramError {
+    /// File is not valid TOML or doesn't have the expected structure.
+    Toml(String),
+    /// A step could not be understood.
+    BadStep { step: String, error: String },
+    /// The program has no steps.
+    Empty
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Toml(e) => write!(f, "invalid program: {}", e),
+            Self::BadStep { step, error } => write!(f, "bad step {:?}: {}", step, error),
+            Self::Empty => write!(f, "program has no steps")
+        }
     }
 }
 
-impl std::error::Error for BadNameError { }
\ No newline at end of file
+impl std::error::Error for ProgramError { }
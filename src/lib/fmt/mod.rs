@@ -2,6 +2,8 @@ use std::time::Duration;
 
 use chrono::{Local, DateTime, NaiveTime};
 
+pub mod parse;
+
 /// Format for date and time.
 pub const DEFAULT_DATETIME_FORMAT: &'static str = "%Y-%m-%d %H:%M:%S";
 /// Format for duration.
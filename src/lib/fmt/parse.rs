@@ -0,0 +1,211 @@
+//! Parse user input.
+
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Days, Local, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::{
+    error::{ParseAutoLapError, ParseDurationError, ParseTimeError},
+    models::{auto_lap::AutoLap, lap::TimeAdjustment}
+};
+
+/// Parse a human-readable duration.
+///
+/// Accepted formats are:
+/// * Numbers with units, like `90s`, `10m`, `1h30m` or `1.5h`. The units are
+///   `d`, `h`, `m`, `s` and `ms`.
+/// * Clock-like durations, like `1:30` (minutes and seconds) or `1:30:00`
+///   (hours, minutes and seconds).
+/// * A plain number, which is read as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, ParseDurationError> {
+    let error = || ParseDurationError { input: input.to_string() };
+    let s = input.trim();
+    if s.is_empty() {
+        return Err(error());
+    }
+    if s.contains(':') {
+        return parse_clock(s).ok_or_else(error);
+    }
+    if let Ok(seconds) = s.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|_| error());
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(error)?;
+        let unit_end = rest[number_end..].find(|c: char| c.is_ascii_digit() || c == '.')
+            .map(|i| number_end + i)
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().map_err(|_| error())?;
+        let unit = match &rest[number_end..unit_end] {
+            "d" => 86400.0,
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return Err(error())
+        };
+        let part = Duration::try_from_secs_f64(number * unit).map_err(|_| error())?;
+        total = total.checked_add(part).ok_or_else(error)?;
+        rest = &rest[unit_end..];
+    }
+    Ok(total)
+}
+
+/// Parse a duration with a `+` or `-` in front, such as `+5m` or `-90s`, into
+/// a [`TimeAdjustment`]. Durations without a sign are added.
+pub fn parse_adjustment(input: &str) -> Result<TimeAdjustment, ParseDurationError> {
+    let s = input.trim();
+    if let Some(amount) = s.strip_prefix('-') {
+        parse_duration(amount).map(TimeAdjustment::Subtract)
+    } else {
+        parse_duration(s.strip_prefix('+').unwrap_or(s)).map(TimeAdjustment::Add)
+    }
+}
+
+/// Parse `mm:ss` or `hh:mm:ss`. Seconds can have a fractional part.
+fn parse_clock(s: &str) -> Option<Duration> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [m, s] => ("0", m, s),
+        [h, m, s] => (h, m, s),
+        _ => return None
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    let whole = Duration::from_secs(hours.checked_mul(3600)?.checked_add(minutes.checked_mul(60)?)?);
+    whole.checked_add(Duration::try_from_secs_f64(seconds).ok()?)
+}
+
+/// Parse a point in time relative to `now`.
+///
+/// Accepted formats are:
+/// * `now`.
+/// * A duration with a minus sign in front, like `-5m`, meaning that long
+///   before `now`. See [`parse_duration`] for how to write durations.
+/// * A time of day in local time, like `14:05` or `14:05:30`. If that time
+///   is later than `now`, it refers to the day before.
+/// * A date and time in local time, like `2023-04-01 14:05` or
+///   `2023-04-01 14:05:30`.
+pub fn parse_time(input: &str, now: SystemTime) -> Result<SystemTime, ParseTimeError> {
+    let error = || ParseTimeError { input: input.to_string() };
+    let s = input.trim();
+    if s == "now" {
+        return Ok(now);
+    }
+    if let Some(ago) = s.strip_prefix('-') {
+        let ago = parse_duration(ago).map_err(|_| error())?;
+        return now.checked_sub(ago).ok_or_else(error);
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return local_to_system_time(datetime).ok_or_else(error);
+        }
+    }
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+            let today = DateTime::<Local>::from(now).date_naive();
+            let at = local_to_system_time(today.and_time(time)).ok_or_else(error)?;
+            if at <= now {
+                return Ok(at);
+            }
+            let yesterday = today.checked_sub_days(Days::new(1)).ok_or_else(error)?;
+            return local_to_system_time(yesterday.and_time(time)).ok_or_else(error);
+        }
+    }
+    Err(error())
+}
+
+/// Parse an [`AutoLap`] policy.
+///
+/// Accepted formats are `hourly`, `daily`, `weekly` or a duration longer than
+/// 0 (see [`parse_duration`]) for a new lap each time the current lap has
+/// played for that long.
+pub fn parse_auto_lap(input: &str) -> Result<AutoLap, ParseAutoLapError> {
+    let error = || ParseAutoLapError { input: input.to_string() };
+    match input.trim() {
+        "hourly" => Ok(AutoLap::Hourly),
+        "daily" => Ok(AutoLap::Daily),
+        "weekly" => Ok(AutoLap::Weekly),
+        s => match parse_duration(s) {
+            Ok(interval) if !interval.is_zero() => Ok(AutoLap::Every(interval)),
+            _ => Err(error())
+        }
+    }
+}
+
+fn local_to_system_time(datetime: NaiveDateTime) -> Option<SystemTime> {
+    Local.from_local_datetime(&datetime).earliest().map(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::{Local, TimeZone};
+
+    use crate::models::{auto_lap::AutoLap, lap::TimeAdjustment};
+
+    use super::{parse_adjustment, parse_auto_lap, parse_duration, parse_time};
+
+    #[test]
+    fn test_units() {
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2m500ms"), Ok(Duration::from_millis(120500)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(parse_duration("1:30"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1:00:05"), Ok(Duration::from_secs(3605)));
+    }
+
+    #[test]
+    fn test_bad() {
+        let too_long = "10000000000000000000s10000000000000000000s";
+        for bad in ["", "m", "10x", "1:2:3:4", "-5m", "1h30", too_long] {
+            assert!(parse_duration(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_adjustment() {
+        let minutes = Duration::from_secs(300);
+        assert_eq!(parse_adjustment("+5m"), Ok(TimeAdjustment::Add(minutes)));
+        assert_eq!(parse_adjustment("5m"), Ok(TimeAdjustment::Add(minutes)));
+        assert_eq!(parse_adjustment("-5:00"), Ok(TimeAdjustment::Subtract(minutes)));
+        assert!(parse_adjustment("--5m").is_err());
+    }
+
+    #[test]
+    fn test_time() {
+        let now: SystemTime = Local.with_ymd_and_hms(2023, 4, 1, 12, 0, 0).unwrap().into();
+        let local = |month, day, h, m| -> SystemTime {
+            Local.with_ymd_and_hms(2023, month, day, h, m, 0).unwrap().into()
+        };
+        assert_eq!(parse_time("now", now), Ok(now));
+        assert_eq!(parse_time("-5m", now), Ok(local(4, 1, 11, 55)));
+        assert_eq!(parse_time("11:30", now), Ok(local(4, 1, 11, 30)));
+        // Later in the day means yesterday
+        assert_eq!(parse_time("13:00", now), Ok(local(3, 31, 13, 0)));
+        assert_eq!(parse_time("2023-04-01 08:15", now), Ok(local(4, 1, 8, 15)));
+        for bad in ["", "+5m", "-", "25:00", "yesterday"] {
+            assert!(parse_time(bad, now).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_auto_lap() {
+        assert_eq!(parse_auto_lap("daily"), Ok(AutoLap::Daily));
+        assert_eq!(parse_auto_lap("1h"), Ok(AutoLap::Every(Duration::from_secs(3600))));
+        for bad in ["", "0s", "monthly", "-1h"] {
+            assert!(parse_auto_lap(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+}
@@ -2,10 +2,12 @@
 
 use std::{fmt, str::FromStr, ops};
 
+use clap::ValueEnum;
 use serde::{Serialize, Deserialize};
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
-use crate::error::BadNameError;
+use crate::error::{BadLapRefError, BadNameError, BadTagError};
 #[allow(unused)]
 use crate::models::stopwatch::Stopwatch; // for see also documentation
 
@@ -58,29 +60,14 @@ impl From<Uuid> for UniqueId {
 pub struct Name(String);
 
 impl Name {
+    /// Check `name` against the default [`NamePolicy`].
     pub fn new<S: Into<String>>(name: S) -> Result<Self, BadNameError> {
-        let name: String = name.into();
-        if bad_name(&name) {
-            Err(BadNameError)
-        } else {
-            Ok(Self(name))
-        }
+        NamePolicy::default().check(&name.into())
     }
 
+    /// Fix `unchecked` so that the default [`NamePolicy`] accepts it.
     pub fn fixed<S: Into<String>>(unchecked: S) -> Self {
-        let mut unchecked: String = unchecked.into();
-        let mut cut = None;
-        for (index, c) in unchecked.chars().enumerate() {
-            if c == '@' {
-                cut = Some(index+1);
-            } else {
-                break;
-            }
-        }
-        if let Some(cut) = cut {
-            unchecked.drain(..cut);
-        }
-        Self(unchecked)
+        NamePolicy::default().fix(&unchecked.into())
     }
 
     /// Create a new [`Name`] without checking to see if it's invalid.
@@ -145,6 +132,348 @@ impl fmt::Display for Identifier {
     }
 }
 
-fn bad_name(name: &str) -> bool {
-    name.starts_with("@")
+/// Characters a [`NamePolicy`] allows in names.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+pub enum NameChars {
+    /// Anything but control characters and whitespace other than spaces.
+    #[default]
+    Printable,
+    /// Anything but control characters and whitespace.
+    NoSpaces,
+    /// Printable ASCII characters, including spaces.
+    Ascii,
+    /// ASCII letters and digits, '-', '_' and '.'.
+    Simple
+}
+
+impl NameChars {
+    /// Whether `c` is allowed.
+    pub fn allows(&self, c: char) -> bool {
+        match self {
+            Self::Printable => !c.is_control() && (c == ' ' || !c.is_whitespace()),
+            Self::NoSpaces => !c.is_control() && !c.is_whitespace(),
+            Self::Ascii => matches!(c, ' '..='~'),
+            Self::Simple => c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+        }
+    }
+}
+
+/// Unicode normalization form that names are put into, so that names that
+/// look the same are the same.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+pub enum Normalization {
+    /// Leave names as they are.
+    Off,
+    /// Canonical composition.
+    #[default]
+    Nfc,
+    /// Compatibility composition, which also turns characters like 'ﬁ' into
+    /// "fi".
+    Nfkc
+}
+
+/// Rules that names have to follow.
+///
+/// Names are normalized and trimmed before they are checked, so
+/// [`NamePolicy::check`] can return a name that is different from the one it
+/// was given. Names that start with '@' or [`TAG_PREFIX`] are never allowed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NamePolicy {
+    pub chars: NameChars,
+    /// Maximum number of characters, or no limit if 0.
+    pub max_length: usize,
+    /// Whether to remove whitespace from both ends.
+    pub trim: bool,
+    pub normalization: Normalization
+}
+
+impl NamePolicy {
+    /// Normalize and trim `name`, then make sure it follows the policy.
+    pub fn check(&self, name: &str) -> Result<Name, BadNameError> {
+        let name = self.lookup(name);
+        if name.starts_with(['@', TAG_PREFIX]) {
+            return Err(BadNameError::Reserved);
+        }
+        if let Some(c) = name.chars().find(|c| !self.chars.allows(*c)) {
+            return Err(BadNameError::BadChar(c));
+        }
+        if self.max_length > 0 && name.chars().count() > self.max_length {
+            return Err(BadNameError::TooLong { max_length: self.max_length });
+        }
+        Ok(name)
+    }
+
+    /// Normalize and trim `name` like [`NamePolicy::check`] does, without
+    /// checking it. Names given to look up a stopwatch go through this so
+    /// that they match the names stopwatches were given.
+    pub fn lookup(&self, name: &str) -> Name {
+        let name = self.normalize(name);
+        Name(if self.trim { name.trim().to_string() } else { name })
+    }
+
+    /// Turn `name` into a name that follows the policy. Whitespace that isn't
+    /// allowed becomes a space or '-', other characters that aren't allowed
+    /// are removed and the name is cut short if it's too long.
+    pub fn fix(&self, name: &str) -> Name {
+        let replacement = if self.chars.allows(' ') { ' ' } else { '-' };
+        let mut fixed: String = self.normalize(name)
+            .chars()
+            .filter_map(|c| match self.chars.allows(c) {
+                true => Some(c),
+                false if c.is_whitespace() => Some(replacement),
+                false => None
+            })
+            .collect();
+        loop {
+            if self.trim {
+                fixed = fixed.trim().to_string();
+            }
+            match fixed.strip_prefix(['@', TAG_PREFIX]) {
+                Some(rest) => fixed = rest.to_string(),
+                None => break
+            }
+        }
+        if self.max_length > 0 {
+            fixed = fixed.chars().take(self.max_length).collect();
+            if self.trim {
+                fixed.truncate(fixed.trim_end().len());
+            }
+        }
+        Name(fixed)
+    }
+
+    /// `name` followed by `-{n}`, cut short if needed so that it still
+    /// follows the policy.
+    pub fn with_suffix(&self, name: &Name, n: usize) -> Result<Name, BadNameError> {
+        let suffix = format!("-{}", n);
+        let base: String = match self.max_length {
+            0 => name.0.clone(),
+            max => name.chars().take(max.saturating_sub(suffix.len())).collect()
+        };
+        self.check(&(base + &suffix))
+    }
+
+    /// `template` with `{n}` replaced by `n`, or followed by `n` if it has no
+    /// `{n}`. The text before the first `{n}`, not counting the separator
+    /// right before it, is cut short if needed so that the number fits.
+    pub fn numbered(&self, template: &str, n: usize) -> Result<Name, BadNameError> {
+        let n = n.to_string();
+        let (before, after) = template.split_once("{n}").unwrap_or((template, ""));
+        let stem = before.trim_end_matches(|c: char| !c.is_alphanumeric());
+        let after = before[stem.len()..].to_string() + &n + &after.replace("{n}", &n);
+        let stem: String = match self.max_length {
+            0 => stem.to_string(),
+            max => stem.chars().take(max.saturating_sub(after.chars().count())).collect()
+        };
+        self.check(&(stem + &after))
+    }
+
+    fn normalize(&self, name: &str) -> String {
+        match self.normalization {
+            Normalization::Off => name.to_string(),
+            Normalization::Nfc => name.nfc().collect(),
+            Normalization::Nfkc => name.nfkc().collect()
+        }
+    }
+}
+
+impl Default for NamePolicy {
+    /// Printable names of up to 64 characters, trimmed and in NFC.
+    fn default() -> Self {
+        Self {
+            chars: NameChars::default(),
+            max_length: 64,
+            trim: true,
+            normalization: Normalization::default()
+        }
+    }
+}
+
+/// Raw identifiers starting with this character select every [`Stopwatch`]
+/// with the [`Tag`] that follows.
+pub const TAG_PREFIX: char = '+';
+
+/// Label used to group [`Stopwatch`]es.
+///
+/// Tags cannot be empty, contain whitespace or commas, or start with
+/// [`TAG_PREFIX`] or '@'.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Tag(String);
+
+impl Tag {
+    pub fn new<S: Into<String>>(tag: S) -> Result<Self, BadTagError> {
+        let tag: String = tag.into();
+        let bad = tag.is_empty()
+            || tag.starts_with(TAG_PREFIX)
+            || tag.starts_with('@')
+            || tag.contains(|c: char| c.is_whitespace() || c == ',');
+        if bad {
+            Err(BadTagError { tag })
+        } else {
+            Ok(Self(tag))
+        }
+    }
+
+    /// Get the tag from a tag selector like `+tag`.
+    pub fn from_selector(selector: &str) -> Option<Self> {
+        Self::new(selector.strip_prefix(TAG_PREFIX)?).ok()
+    }
+
+    /// Turn this tag into a selector like `+tag`.
+    pub fn to_selector(&self) -> String {
+        format!("{}{}", TAG_PREFIX, self.0)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = BadTagError;
+
+    /// Parse a tag, with or without [`TAG_PREFIX`] in front.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s.strip_prefix(TAG_PREFIX).unwrap_or(s))
+    }
+}
+
+impl ops::Deref for Tag {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Way to refer to a lap of a [`Stopwatch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LapRef {
+    /// Lap number, starting from 1 for the first lap and including the
+    /// current lap.
+    Number(usize),
+    /// Lap id, shortened from the lap's [`Uuid`] the same way a [`UniqueId`]
+    /// is.
+    Id(UniqueId)
+}
+
+impl LapRef {
+    /// Whether this refers to the lap numbered `number` with the id `id`.
+    pub fn matches(&self, number: usize, id: &Uuid) -> bool {
+        match self {
+            Self::Number(n) => *n == number,
+            Self::Id(short) => *short == UniqueId::from(*id)
+        }
+    }
+}
+
+impl Default for LapRef {
+    fn default() -> Self {
+        Self::Number(1)
+    }
+}
+
+impl FromStr for LapRef {
+    type Err = BadLapRefError;
+
+    /// Parse a lap number like `2` or a lap id like `@6f1ac5b2d87e`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = UniqueId::from_str(s) {
+            return Ok(Self::Id(id));
+        }
+        match s.parse() {
+            Ok(number) if number > 0 => Ok(Self::Number(number)),
+            _ => Err(BadLapRefError { lap_ref: s.to_string() })
+        }
+    }
+}
+
+impl fmt::Display for LapRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Id(id) => write!(f, "{}", id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use crate::error::BadNameError;
+
+    use super::{LapRef, Name, NameChars, NamePolicy, Normalization, Tag, UniqueId};
+
+    #[test]
+    fn test_tag() {
+        assert_eq!("+work".parse(), Tag::new("work"));
+        assert_eq!(Tag::from_selector("+work"), Tag::new("work").ok());
+        assert_eq!(Tag::from_selector("work"), None);
+        for bad in ["", "+", "@a", "a b", "a,b"] {
+            assert!(Tag::new(bad).is_err(), "{:?} should not be a tag", bad);
+        }
+    }
+
+    #[test]
+    fn test_lap_ref() {
+        let uuid = Uuid::new_v4();
+        let id = UniqueId::from(uuid);
+        assert_eq!("3".parse(), Ok(LapRef::Number(3)));
+        assert_eq!(id.to_string().parse(), Ok(LapRef::Id(id)));
+        assert!(LapRef::Id(id).matches(5, &uuid));
+        assert!(!LapRef::Number(4).matches(5, &uuid));
+        for bad in ["0", "-1", "@xyz", "lap"] {
+            assert!(bad.parse::<LapRef>().is_err(), "{:?} should not be a lap", bad);
+        }
+    }
+
+    #[test]
+    fn test_name_not_selector() {
+        assert!(Name::new("+work").is_err());
+        assert_eq!(&*Name::fixed("@+work"), "work");
+    }
+
+    #[test]
+    fn test_name_policy() {
+        let policy = NamePolicy::default();
+        assert_eq!(policy.check("  deep work "), Name::new("deep work"));
+        assert_eq!(policy.check("a\nb"), Err(BadNameError::BadChar('\n')));
+        assert_eq!(policy.check("a\tb"), Err(BadNameError::BadChar('\t')));
+        assert_eq!(policy.check(" @a"), Err(BadNameError::Reserved));
+        assert_eq!(policy.check(&"a".repeat(65)), Err(BadNameError::TooLong { max_length: 64 }));
+        assert_eq!(policy.check(""), Ok(Name::default()));
+        // "e" followed by a combining acute accent
+        assert_eq!(policy.check("cafe\u{301}"), policy.check("caf\u{e9}"));
+        assert_eq!(policy.lookup(" cafe\u{301}"), policy.check("caf\u{e9}").unwrap());
+        assert_eq!(&*policy.lookup("@a"), "@a");
+
+        assert_eq!(&*policy.fix("a\tb\u{7}c\n"), "a bc");
+        assert_eq!(&*policy.fix(" @ +x"), "x");
+        assert_eq!(policy.fix(&"a".repeat(100)).len(), 64);
+        let suffixed = policy.with_suffix(&Name::fixed("a".repeat(64)), 2).unwrap();
+        assert_eq!(&*suffixed, "a".repeat(62) + "-2");
+        let tiny = NamePolicy { max_length: 1, ..policy };
+        let too_long = Err(BadNameError::TooLong { max_length: 1 });
+        assert_eq!(tiny.with_suffix(&Name::fixed("a"), 2), too_long);
+        let long = "a".repeat(100) + "-{n}";
+        assert_eq!(&*policy.numbered(&long, 10).unwrap(), "a".repeat(61) + "-10");
+        assert_eq!(&*policy.numbered("{n}-{n}", 3).unwrap(), "3-3");
+        assert!(policy.numbered(&("{n}".to_string() + &"a".repeat(64)), 1).is_err());
+
+        let strict = NamePolicy {
+            chars: NameChars::Simple,
+            max_length: 0,
+            trim: false,
+            normalization: Normalization::Nfkc
+        };
+        assert_eq!(strict.check(" a"), Err(BadNameError::BadChar(' ')));
+        assert_eq!(&*strict.check(&"\u{fb01}".repeat(100)).unwrap(), "fi".repeat(100));
+        assert_eq!(&*strict.fix("work out!"), "work-out");
+        for c in ['a', 'Z', '0', '-', '_', '.'] {
+            assert!(strict.chars.allows(c));
+        }
+    }
 }
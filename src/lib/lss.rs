@@ -0,0 +1,419 @@
+//! Read and write LiveSplit splits files (`.lss`).
+//!
+//! Only the parts of the file that Stopwatchd has a use for are read, which
+//! are the names of the segments and the real time of each attempt in the
+//! attempt history. Game time, icons, comparisons other than the personal best
+//! and auto splitter settings are ignored.
+
+use std::{fmt::Write, time::{Duration, SystemTime}};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use roxmltree::{Document, Node};
+
+use crate::{
+    error::LssError,
+    models::lap::{FinishedLap, LapAnnotation}
+};
+
+/// Format of the dates in the attempt history.
+const LSS_DATETIME_FORMAT: &str = "%m/%d/%Y %H:%M:%S";
+
+/// Contents of a splits file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Splits {
+    pub game_name: String,
+    pub category_name: String,
+    /// Names of the segments, in order.
+    pub segments: Vec<String>,
+    pub attempts: Vec<Attempt>
+}
+
+/// One attempt from the attempt history of a splits file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attempt {
+    pub id: i64,
+    /// When the attempt started, if LiveSplit recorded it.
+    pub started: Option<SystemTime>,
+    /// How long each segment took, up to the segment the attempt was reset
+    /// on. Segments that were skipped are [`None`].
+    pub segments: Vec<Option<Duration>>
+}
+
+impl Attempt {
+    /// Whether every one of `segments_count` segments was finished.
+    pub fn is_complete(&self, segments_count: usize) -> bool {
+        self.segments.len() >= segments_count && self.segments.last().copied().flatten().is_some()
+    }
+
+    /// Turn the finished segments of this attempt into laps labelled with the
+    /// names of the segments. The time of a skipped segment is counted in the
+    /// next segment that was finished, so they share a lap.
+    pub fn laps(&self, names: &[String]) -> Vec<(Duration, LapAnnotation)> {
+        let mut laps = vec![];
+        let mut skipped: Vec<&str> = vec![];
+        for (index, time) in self.segments.iter().enumerate() {
+            let name = names.get(index).map(|n| n.as_str()).unwrap_or_default();
+            skipped.push(name);
+            if let Some(time) = time {
+                let label = Some(skipped.join(" + "));
+                laps.push((*time, LapAnnotation { label, note: None }));
+                skipped.clear();
+            }
+        }
+        laps
+    }
+
+    /// Time spent on the segments that were finished, or [`None`] if it's
+    /// too long to add up.
+    pub fn total_time(&self) -> Option<Duration> {
+        self.segments.iter()
+            .flatten()
+            .try_fold(Duration::ZERO, |total, time| total.checked_add(*time))
+    }
+}
+
+impl Splits {
+    /// Read the contents of a splits file.
+    pub fn parse(xml: &str) -> Result<Self, LssError> {
+        let document = Document::parse(xml).map_err(|e| LssError::Xml(e.to_string()))?;
+        let run = document.root_element();
+        if !run.has_tag_name("Run") {
+            return Err(LssError::Malformed("root element is not <Run>".to_string()));
+        }
+        let game_name = child_text(run, "GameName").unwrap_or_default().to_string();
+        let category_name = child_text(run, "CategoryName").unwrap_or_default().to_string();
+
+        let segment_nodes: Vec<Node> = match child(run, "Segments") {
+            Some(segments) => segments.children().filter(|n| n.has_tag_name("Segment")).collect(),
+            None => vec![]
+        };
+        let segments = segment_nodes.iter()
+            .map(|s| child_text(*s, "Name").unwrap_or_default().to_string())
+            .collect();
+
+        let mut attempts = vec![];
+        let attempt_nodes = child(run, "AttemptHistory").into_iter()
+            .flat_map(|history| history.children())
+            .filter(|n| n.has_tag_name("Attempt"));
+        for node in attempt_nodes {
+            let id = parse_id(node)?;
+            let started = match node.attribute("started") {
+                Some(started) => Some(parse_datetime(started)?),
+                None => None
+            };
+            let mut times = vec![];
+            for segment in &segment_nodes {
+                let time = child(*segment, "SegmentHistory").into_iter()
+                    .flat_map(|history| history.children())
+                    .find(|t| t.has_tag_name("Time") && t.attribute("id") == Some(&id.to_string()));
+                // No time for this segment means the attempt was reset before
+                // reaching it
+                let time = match time {
+                    Some(time) => time,
+                    None => break
+                };
+                times.push(match child_text(time, "RealTime") {
+                    Some(real_time) => Some(parse_lss_time(real_time)?),
+                    None => None
+                });
+            }
+            attempts.push(Attempt { id, started, segments: times });
+        }
+
+        Ok(Self { game_name, category_name, segments, attempts })
+    }
+
+    /// Make splits with a single attempt out of the laps of a stopwatch. Each
+    /// lap becomes a segment named after the label of the lap.
+    pub fn from_laps<S: Into<String>>(name: S, laps: &[FinishedLap]) -> Self {
+        let segments = laps.iter()
+            .enumerate()
+            .map(|(index, lap)| lap.label.clone().unwrap_or_else(|| format!("Lap {}", index + 1)))
+            .collect();
+        let attempt = Attempt {
+            id: 1,
+            started: laps.first().map(|lap| lap.start),
+            segments: laps.iter().map(|lap| Some(lap.duration)).collect()
+        };
+        Self {
+            game_name: name.into(),
+            category_name: "Stopwatchd".to_string(),
+            segments,
+            attempts: vec![attempt]
+        }
+    }
+
+    /// Fastest attempt that finished every segment.
+    pub fn personal_best(&self) -> Option<&Attempt> {
+        self.attempts.iter()
+            .filter(|a| a.is_complete(self.segments.len()))
+            .filter_map(|a| Some((a, a.total_time()?)))
+            .min_by_key(|(_, total)| *total)
+            .map(|(a, _)| a)
+    }
+
+    /// Write these splits as a splits file.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        // Writing to a String can't fail
+        let _ = self.write_xml(&mut xml);
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) -> std::fmt::Result {
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(xml, r#"<Run version="1.7.0">"#)?;
+        writeln!(xml, "  <GameIcon />")?;
+        writeln!(xml, "  <GameName>{}</GameName>", escape(&self.game_name))?;
+        writeln!(xml, "  <CategoryName>{}</CategoryName>", escape(&self.category_name))?;
+        writeln!(xml, "  <Offset>00:00:00</Offset>")?;
+        writeln!(xml, "  <AttemptCount>{}</AttemptCount>", self.attempts.len())?;
+
+        writeln!(xml, "  <AttemptHistory>")?;
+        for attempt in &self.attempts {
+            write!(xml, r#"    <Attempt id="{}""#, attempt.id)?;
+            let total = attempt.total_time();
+            if let Some(started) = attempt.started {
+                write!(xml, r#" started="{}" isStartedSynced="False""#, format_datetime(started))?;
+                // Leave out when the attempt ended if it's too far ahead
+                let ended = total.and_then(|total| started.checked_add(total))
+                    .filter(|ended| *ended <= SystemTime::from(DateTime::<Utc>::MAX_UTC));
+                if let Some(ended) = ended {
+                    write!(xml, r#" ended="{}" isEndedSynced="False""#, format_datetime(ended))?;
+                }
+            }
+            match total {
+                Some(total) if attempt.is_complete(self.segments.len()) => {
+                    writeln!(xml, ">")?;
+                    writeln!(xml, "      <RealTime>{}</RealTime>", format_lss_time(total))?;
+                    writeln!(xml, "    </Attempt>")?;
+                },
+                _ => writeln!(xml, " />")?
+            }
+        }
+        writeln!(xml, "  </AttemptHistory>")?;
+
+        let personal_best = self.personal_best();
+        let mut pb_split = Duration::ZERO;
+        writeln!(xml, "  <Segments>")?;
+        for (index, name) in self.segments.iter().enumerate() {
+            writeln!(xml, "    <Segment>")?;
+            writeln!(xml, "      <Name>{}</Name>", escape(name))?;
+            writeln!(xml, "      <Icon />")?;
+            writeln!(xml, "      <SplitTimes>")?;
+            match personal_best.and_then(|pb| pb.segments.get(index).copied().flatten()) {
+                Some(time) => {
+                    pb_split += time;
+                    writeln!(xml, r#"        <SplitTime name="Personal Best">"#)?;
+                    writeln!(xml, "          <RealTime>{}</RealTime>", format_lss_time(pb_split))?;
+                    writeln!(xml, "        </SplitTime>")?;
+                },
+                None => writeln!(xml, r#"        <SplitTime name="Personal Best" />"#)?
+            }
+            writeln!(xml, "      </SplitTimes>")?;
+            let best = self.attempts.iter()
+                .filter_map(|a| a.segments.get(index).copied().flatten())
+                .min();
+            match best {
+                Some(best) => {
+                    writeln!(xml, "      <BestSegmentTime>")?;
+                    writeln!(xml, "        <RealTime>{}</RealTime>", format_lss_time(best))?;
+                    writeln!(xml, "      </BestSegmentTime>")?;
+                },
+                None => writeln!(xml, "      <BestSegmentTime />")?
+            }
+            writeln!(xml, "      <SegmentHistory>")?;
+            for attempt in &self.attempts {
+                match attempt.segments.get(index) {
+                    Some(Some(time)) => {
+                        writeln!(xml, r#"        <Time id="{}">"#, attempt.id)?;
+                        writeln!(xml, "          <RealTime>{}</RealTime>", format_lss_time(*time))?;
+                        writeln!(xml, "        </Time>")?;
+                    },
+                    Some(None) => writeln!(xml, r#"        <Time id="{}" />"#, attempt.id)?,
+                    None => {}
+                }
+            }
+            writeln!(xml, "      </SegmentHistory>")?;
+            writeln!(xml, "    </Segment>")?;
+        }
+        writeln!(xml, "  </Segments>")?;
+        writeln!(xml, "  <AutoSplitterSettings />")?;
+        writeln!(xml, "</Run>")
+    }
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, tag: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag).and_then(|n| n.text()).map(str::trim)
+}
+
+fn parse_id(node: Node) -> Result<i64, LssError> {
+    node.attribute("id")
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| LssError::Malformed("attempt without a valid id".to_string()))
+}
+
+fn parse_datetime(input: &str) -> Result<SystemTime, LssError> {
+    NaiveDateTime::parse_from_str(input, LSS_DATETIME_FORMAT)
+        .map(|naive| DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc).into())
+        .map_err(|_| LssError::Malformed(format!("invalid date: {:?}", input)))
+}
+
+fn format_datetime(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).format(LSS_DATETIME_FORMAT).to_string()
+}
+
+/// Parse a time like `01:23:45.6780000` or `1.01:23:45.6780000` (with days).
+pub fn parse_lss_time(input: &str) -> Result<Duration, LssError> {
+    let bad = || LssError::Malformed(format!("invalid time: {:?}", input));
+    let parts: Vec<&str> = input.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return Err(bad())
+    };
+    let (days, hours) = match hours.split_once('.') {
+        Some((days, hours)) => (days.parse::<u64>().map_err(|_| bad())?, hours),
+        None => (0, hours)
+    };
+    let hours: u64 = hours.parse().map_err(|_| bad())?;
+    let minutes: u64 = minutes.parse().map_err(|_| bad())?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let seconds: u64 = seconds.parse().map_err(|_| bad())?;
+    let nanos = if fraction.is_empty() {
+        0
+    } else if fraction.len() <= 9 && fraction.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{:0<9}", fraction).parse::<u32>().map_err(|_| bad())?
+    } else {
+        return Err(bad());
+    };
+    let seconds = days.checked_mul(24)
+        .and_then(|hours_in_days| hours_in_days.checked_add(hours))
+        .and_then(|hours| hours.checked_mul(60)?.checked_add(minutes))
+        .and_then(|minutes| minutes.checked_mul(60)?.checked_add(seconds))
+        .ok_or_else(bad)?;
+    Ok(Duration::new(seconds, nanos))
+}
+
+/// Format a duration the same way LiveSplit does, like `01:23:45.6780000`.
+pub fn format_lss_time(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let days = seconds / 86400;
+    let time = format!(
+        "{:02}:{:02}:{:02}.{:07}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        duration.subsec_nanos() / 100
+    );
+    if days > 0 {
+        format!("{}.{}", days, time)
+    } else {
+        time
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Splits, format_lss_time, parse_lss_time};
+
+    const SPLITS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <GameName>Some &amp; Game</GameName>
+  <CategoryName>Any%</CategoryName>
+  <AttemptHistory>
+    <Attempt id="1" started="04/01/2023 14:05:00" isStartedSynced="True" ended="04/01/2023 14:06:00" isEndedSynced="True">
+      <RealTime>00:00:50.0000000</RealTime>
+    </Attempt>
+    <Attempt id="2" started="04/02/2023 09:00:00" isStartedSynced="True" ended="04/02/2023 09:00:30" isEndedSynced="True" />
+    <Attempt id="3" />
+  </AttemptHistory>
+  <Segments>
+    <Segment>
+      <Name>first</Name>
+      <SegmentHistory>
+        <Time id="1"><RealTime>00:00:10.5000000</RealTime></Time>
+        <Time id="2" />
+      </SegmentHistory>
+    </Segment>
+    <Segment>
+      <Name>second</Name>
+      <SegmentHistory>
+        <Time id="1"><RealTime>00:00:20.0000000</RealTime></Time>
+        <Time id="2"><RealTime>00:00:25.0000000</RealTime></Time>
+      </SegmentHistory>
+    </Segment>
+    <Segment>
+      <Name>third</Name>
+      <SegmentHistory>
+        <Time id="1"><RealTime>00:00:19.5000000</RealTime></Time>
+      </SegmentHistory>
+    </Segment>
+  </Segments>
+</Run>"#;
+
+    #[test]
+    fn test_parse() {
+        let secs = Duration::from_secs_f64;
+        let splits = Splits::parse(SPLITS).unwrap();
+        assert_eq!(splits.game_name, "Some & Game");
+        assert_eq!(splits.segments, ["first", "second", "third"]);
+        assert_eq!(splits.attempts.len(), 3);
+        assert_eq!(
+            splits.attempts[0].segments,
+            [Some(secs(10.5)), Some(secs(20.0)), Some(secs(19.5))]
+        );
+        assert!(splits.attempts[0].started.is_some());
+        assert!(splits.attempts[0].is_complete(3));
+        // Skipped the first segment, then reset on the third
+        assert_eq!(splits.attempts[1].segments, [None, Some(secs(25.0))]);
+        let laps = splits.attempts[1].laps(&splits.segments);
+        assert_eq!(laps.len(), 1);
+        assert_eq!(laps[0].1.label.as_deref(), Some("first + second"));
+        assert!(splits.attempts[2].segments.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let splits = Splits::parse(SPLITS).unwrap();
+        let written = Splits::parse(&splits.to_xml()).unwrap();
+        assert_eq!(splits, written);
+    }
+
+    #[test]
+    fn test_lss_time() {
+        let time = Duration::new(3 * 86400 + 3723, 456_000_000);
+        assert_eq!(format_lss_time(time), "3.01:02:03.4560000");
+        assert_eq!(parse_lss_time("3.01:02:03.4560000").unwrap(), time);
+        assert_eq!(parse_lss_time("00:01:30").unwrap(), Duration::from_secs(90));
+        assert!(parse_lss_time("1:30").is_err());
+        assert!(parse_lss_time("999999999999999.00:00:00").is_err());
+    }
+
+    #[test]
+    fn test_oversized_times() {
+        let huge = parse_lss_time("200000000000000.00:00:00").unwrap();
+        let xml = SPLITS.replace("00:00:20.0000000", "200000000000000.00:00:00")
+            .replace("00:00:19.5000000", "200000000000000.00:00:00");
+        let splits = Splits::parse(&xml).unwrap();
+        assert_eq!(splits.attempts[0].segments[1], Some(huge));
+        assert_eq!(splits.attempts[0].total_time(), None);
+        assert!(splits.personal_best().is_none());
+        let written = splits.to_xml();
+        let first = written.lines().find(|line| line.contains(r#"<Attempt id="1""#)).unwrap();
+        assert!(!first.contains("ended="));
+        assert!(Splits::parse(&written).is_ok());
+    }
+}
@@ -8,6 +8,8 @@ pub mod error;
 pub mod fmt;
 pub mod identifiers;
 pub mod logging;
+#[cfg(feature = "lss")]
+pub mod lss;
 pub mod models;
 pub mod pidfile;
 pub mod runtime;
@@ -0,0 +1,99 @@
+//! Policies for ending laps without being asked to.
+//!
+//! A [`Stopwatch`] with an [`AutoLap`] policy starts a new lap by itself,
+//! either after the current lap has played for a set amount of time or when
+//! the local wall-clock time crosses a boundary, like midnight. `swd` enforces
+//! the policy with
+//! [`Stopwatch::check_auto_lap`](super::stopwatch::Stopwatch::check_auto_lap).
+
+use std::{fmt, time::{Duration, SystemTime}};
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::{Serialize, Deserialize};
+
+#[allow(unused)]
+use super::stopwatch::Stopwatch; // for see also documentation
+
+/// When a [`Stopwatch`] should start a new lap by itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoLap {
+    /// After the current lap has played for this long.
+    Every(Duration),
+    /// At the start of every hour in local time.
+    Hourly,
+    /// At local midnight.
+    Daily,
+    /// At local midnight between Sunday and Monday.
+    Weekly
+}
+
+impl AutoLap {
+    /// How long each lap plays for, if the policy doesn't follow the clock.
+    pub fn interval(&self) -> Option<Duration> {
+        match self {
+            Self::Every(interval) => Some(*interval),
+            _ => None
+        }
+    }
+
+    /// The first boundary strictly after `after`, if the policy follows the
+    /// clock.
+    pub fn next_boundary(&self, after: SystemTime) -> Option<SystemTime> {
+        let after = DateTime::<Local>::from(after).naive_local();
+        let date = after.date();
+        let hour = chrono::Duration::hours(1);
+        let boundary = match self {
+            Self::Every(_) => return None,
+            Self::Hourly => date.and_hms_opt(after.hour(), 0, 0)? + hour,
+            Self::Daily => midnight(date.checked_add_days(Days::new(1))?),
+            Self::Weekly => {
+                let days = 7 - date.weekday().num_days_from_monday();
+                midnight(date.checked_add_days(Days::new(days as u64))?)
+            }
+        };
+        // Skip over times that don't exist because of daylight saving
+        Local.from_local_datetime(&boundary).earliest()
+            .or_else(|| Local.from_local_datetime(&(boundary + hour)).earliest())
+            .map(Into::into)
+    }
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).unwrap()
+}
+
+impl fmt::Display for AutoLap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(interval) => write!(f, "every {:?}", interval),
+            Self::Hourly => write!(f, "hourly"),
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::{Local, TimeZone};
+
+    use super::AutoLap;
+
+    #[test]
+    fn test_next_boundary() {
+        let local = |day, h, m| -> SystemTime {
+            Local.with_ymd_and_hms(2023, 4, day, h, m, 0).unwrap().into()
+        };
+        // 2023-04-05 is a Wednesday
+        let now = local(5, 14, 30);
+        assert_eq!(AutoLap::Every(Duration::from_secs(60)).next_boundary(now), None);
+        assert_eq!(AutoLap::Hourly.next_boundary(now), Some(local(5, 15, 0)));
+        assert_eq!(AutoLap::Daily.next_boundary(now), Some(local(6, 0, 0)));
+        assert_eq!(AutoLap::Weekly.next_boundary(now), Some(local(10, 0, 0)));
+        // Boundaries are strictly later
+        assert_eq!(AutoLap::Daily.next_boundary(local(6, 0, 0)), Some(local(7, 0, 0)));
+        assert_eq!(AutoLap::Weekly.next_boundary(local(10, 0, 0)), Some(local(17, 0, 0)));
+    }
+}
@@ -5,20 +5,30 @@ use std::time::{SystemTime, Instant, Duration};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
-use crate::identifiers::UniqueId;
+use crate::{error::LapError, identifiers::UniqueId};
 
-use super::stopwatch::State;
+use super::{snapshot::CurrentLapSnapshot, stopwatch::State};
 #[allow(unused)]
 use super::stopwatch::Stopwatch;
 
 /// The active lap that is ticking.
-#[derive(Debug)]
+///
+/// [`Instant`]s cannot be serialized, so a [`CurrentLap`] is stored as a
+/// [`CurrentLapSnapshot`] instead.
+#[derive(Clone, Debug)]
 pub struct CurrentLap {
     pub id: Uuid,
     pub sw_id: UniqueId,
     pub start: SystemTime,
     timer: Option<Instant>,
-    pub duration: Duration
+    /// Wall-clock time when the lap last started playing. Only [`Some`] while
+    /// the lap is playing.
+    segment_start: Option<SystemTime>,
+    pub duration: Duration,
+    /// Periods of time this lap was playing, not including the ongoing one.
+    pub segments: Vec<Segment>,
+    pub label: Option<String>,
+    pub note: Option<String>
 }
 
 impl CurrentLap {
@@ -31,7 +41,17 @@ impl CurrentLap {
         let start = SystemTime::now();
         let timer = None;
         let duration = Duration::new(0, 0);
-        Self { id, sw_id, start, timer, duration }
+        Self {
+            id,
+            sw_id,
+            start,
+            timer,
+            segment_start: None,
+            duration,
+            segments: vec![],
+            label: None,
+            note: None
+        }
     }
 
     /// Start the timer on this lap.
@@ -45,14 +65,107 @@ impl CurrentLap {
     pub fn play(&mut self) {
         if let None = self.timer {
             self.timer = Some(Instant::now());
+            self.segment_start = Some(SystemTime::now());
         }
     }
 
+    /// Like [`CurrentLap::new`] but as if the lap was created at `at`.
+    pub fn new_at(sw_id: UniqueId, at: SystemTime) -> Self {
+        let mut lap = Self::new(sw_id);
+        lap.start = at;
+        lap
+    }
+
+    /// Like [`CurrentLap::start`] but as if the lap was started at `at`.
+    pub fn start_at(sw_id: UniqueId, at: SystemTime) -> Result<Self, LapError> {
+        let mut lap = Self::new_at(sw_id, at);
+        lap.play_at(at)?;
+        Ok(lap)
+    }
+
+    /// Like [`CurrentLap::play`] but as if the lap continued at `at`.
+    ///
+    /// `at` cannot be in the future or before the last time the lap was
+    /// paused.
+    pub fn play_at(&mut self, at: SystemTime) -> Result<(), LapError> {
+        if self.playing() {
+            return Ok(());
+        }
+        let ago = self.check_time(at)?;
+        self.timer = Some(Instant::now());
+        self.segment_start = Some(at);
+        self.duration = self.duration.saturating_add(ago);
+        Ok(())
+    }
+
+    /// Like [`CurrentLap::pause`] but as if the lap was paused at `at`.
+    ///
+    /// `at` cannot be in the future or before the last time the lap was
+    /// played.
+    pub fn pause_at(&mut self, at: SystemTime) -> Result<(), LapError> {
+        if !self.playing() {
+            return Ok(());
+        }
+        let ago = self.check_time(at)?;
+        self.pause();
+        self.duration = self.duration.saturating_sub(ago);
+        if let Some(segment) = self.segments.last_mut() {
+            segment.end = at;
+        }
+        Ok(())
+    }
+
+    /// When this lap was last played or paused.
+    pub fn last_change(&self) -> SystemTime {
+        self.segment_start
+            .or_else(|| self.segments.last().map(|s| s.end))
+            .unwrap_or(self.start)
+    }
+
+    /// Make sure `at` is between [`CurrentLap::last_change`] and now, and
+    /// return how long ago `at` was.
+    pub fn check_time(&self, at: SystemTime) -> Result<Duration, LapError> {
+        let earliest = self.last_change();
+        if at < earliest {
+            return Err(LapError::TooEarly { earliest });
+        }
+        SystemTime::now().duration_since(at).map_err(|_| LapError::FutureTime)
+    }
+
+    /// Add or take away time from this lap. The segments of the lap are left
+    /// as they are.
+    pub fn adjust(&mut self, adjustment: TimeAdjustment) -> Result<(), TimeAdjustment> {
+        let total = adjustment.apply(self.total_time())?;
+        if self.playing() {
+            self.timer = Some(Instant::now());
+        }
+        self.duration = total;
+        Ok(())
+    }
+
     /// Temporarily stop the timer.
     pub fn pause(&mut self) {
+        if let Some(segment) = self.ongoing_segment() {
+            self.segments.push(segment);
+        }
         if let Some(timer) = self.timer.take() {
-            self.duration += timer.elapsed();
+            self.duration = self.duration.saturating_add(timer.elapsed());
         }
+        self.segment_start = None;
+    }
+
+    /// The segment that is still running if the lap is playing. It ends at
+    /// the current time.
+    pub fn ongoing_segment(&self) -> Option<Segment> {
+        let start = self.segment_start?;
+        Some(Segment { start, end: SystemTime::now() })
+    }
+
+    /// All segments of this lap, including the ongoing one.
+    pub fn all_segments(&self) -> Vec<Segment> {
+        let mut segments = self.segments.clone();
+        segments.extend(self.ongoing_segment());
+        segments
     }
 
     /// Check if the current lap is playing.
@@ -77,24 +190,90 @@ impl CurrentLap {
             id: self.id,
             sw_id: self.sw_id,
             start: self.start,
-            duration: self.total_time()
+            duration: self.total_time(),
+            segments: self.all_segments(),
+            label: self.label.clone(),
+            note: self.note.clone()
         }
     }
 
+    /// Change the label and note of this lap.
+    pub fn annotate(&mut self, annotation: &LapAnnotation) {
+        annotation.apply(&mut self.label, &mut self.note);
+    }
+
+    /// Merge `previous`, the lap just before this one, into this lap. The
+    /// merged lap takes the id and start time of `previous`, and its label
+    /// and note too if it has them.
+    pub fn merge_previous(&mut self, previous: FinishedLap) {
+        previous.merge_into(
+            &mut self.id,
+            &mut self.start,
+            &mut self.duration,
+            &mut self.segments,
+            &mut self.label,
+            &mut self.note
+        );
+    }
+
     /// The total time this lap has been running for.
     pub fn total_time(&self) -> Duration {
         if let Some(ref timer) = self.timer {
-            self.duration + timer.elapsed()
+            self.duration.saturating_add(timer.elapsed())
         } else {
             self.duration
         }
     }
 }
 
+impl From<CurrentLap> for CurrentLapSnapshot {
+    fn from(lap: CurrentLap) -> Self {
+        let playing_since = if lap.playing() { Some(SystemTime::now()) } else { None };
+        let duration = lap.total_time();
+        Self {
+            id: lap.id,
+            sw_id: lap.sw_id,
+            start: lap.start,
+            duration,
+            playing_since,
+            segment_start: lap.segment_start,
+            segments: lap.segments,
+            label: lap.label,
+            note: lap.note
+        }
+    }
+}
+
+impl From<CurrentLapSnapshot> for CurrentLap {
+    fn from(snapshot: CurrentLapSnapshot) -> Self {
+        let CurrentLapSnapshot {
+            id, sw_id, start, mut duration, playing_since, segment_start, segments, label, note
+        } = snapshot;
+        let timer = playing_since.map(|since| {
+            // If the clock went backwards, don't count the time in between.
+            let since = SystemTime::now().duration_since(since).unwrap_or_default();
+            duration = duration.saturating_add(since);
+            Instant::now()
+        });
+        // The ongoing segment carries on from before the snapshot
+        let segment_start = timer.and(segment_start.or(playing_since));
+        Self { id, sw_id, start, timer, segment_start, duration, segments, label, note }
+    }
+}
+
 impl Into<FinishedLap> for CurrentLap {
     fn into(self) -> FinishedLap {
         let duration = self.total_time();
-        FinishedLap { id: self.id, sw_id: self.sw_id, start: self.start, duration }
+        let segments = self.all_segments();
+        FinishedLap {
+            id: self.id,
+            sw_id: self.sw_id,
+            start: self.start,
+            duration,
+            segments,
+            label: self.label,
+            note: self.note
+        }
     }
 }
 
@@ -104,5 +283,311 @@ pub struct FinishedLap {
     pub id: Uuid,
     pub sw_id: UniqueId,
     pub start: SystemTime,
-    pub duration: Duration
+    pub duration: Duration,
+    /// Periods of time this lap was playing.
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    /// Short name for the lap.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Free-text description of the lap.
+    #[serde(default)]
+    pub note: Option<String>
+}
+
+impl FinishedLap {
+    /// Merge this lap into the fields of the lap just after it. Used by both
+    /// [`CurrentLap::merge_previous`] and [`FinishedLap::merge_previous`].
+    fn merge_into(
+        self,
+        id: &mut Uuid,
+        start: &mut SystemTime,
+        duration: &mut Duration,
+        segments: &mut Vec<Segment>,
+        label: &mut Option<String>,
+        note: &mut Option<String>
+    ) {
+        *id = self.id;
+        *start = self.start;
+        *duration += self.duration;
+        segments.splice(0..0, self.segments);
+        *label = self.label.or(label.take());
+        *note = self.note.or(note.take());
+    }
+
+    /// Change the label and note of this lap.
+    pub fn annotate(&mut self, annotation: &LapAnnotation) {
+        annotation.apply(&mut self.label, &mut self.note);
+    }
+
+    /// Add or take away time from this lap. The segments of the lap are left
+    /// as they are.
+    pub fn adjust(&mut self, adjustment: TimeAdjustment) -> Result<(), TimeAdjustment> {
+        self.duration = adjustment.apply(self.duration)?;
+        Ok(())
+    }
+
+    /// Merge `previous`, the lap just before this one, into this lap. The
+    /// merged lap takes the id and start time of `previous`, and its label
+    /// and note too if it has them.
+    pub fn merge_previous(&mut self, previous: FinishedLap) {
+        previous.merge_into(
+            &mut self.id,
+            &mut self.start,
+            &mut self.duration,
+            &mut self.segments,
+            &mut self.label,
+            &mut self.note
+        );
+    }
+
+    /// Split this lap after it has been playing for `offset`. This lap keeps
+    /// the time before `offset` and the rest is returned as a new lap without
+    /// a label or note. Nothing happens if `offset` isn't shorter than the lap.
+    pub fn split(&mut self, offset: Duration) -> Option<FinishedLap> {
+        if offset.is_zero() || offset >= self.duration {
+            return None;
+        }
+        // Find the segment that was playing when `offset` was reached
+        let mut played = Duration::ZERO;
+        let mut split_at = None;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let length = segment.duration();
+            if played.saturating_add(length) >= offset {
+                split_at = Some((index, segment.start + (offset - played)));
+                break;
+            }
+            played = played.saturating_add(length);
+        }
+        let (segments, start) = match split_at {
+            Some((index, at)) => {
+                let mut rest = self.segments.split_off(index);
+                if rest[0].start < at {
+                    self.segments.push(Segment { start: rest[0].start, end: at });
+                }
+                rest[0].start = at;
+                rest.retain(|segment| segment.start < segment.end);
+                (rest, at)
+            },
+            // Segments are left as they are when time is added or taken away
+            // from a lap, so they might not add up to `duration`
+            None => {
+                let start = self.start.checked_add(offset).unwrap_or(self.start);
+                (vec![], self.segments.last().map_or(start, |s| s.end))
+            }
+        };
+        let duration = self.duration - offset;
+        self.duration = offset;
+        Some(FinishedLap {
+            id: Uuid::new_v4(),
+            sw_id: self.sw_id,
+            start,
+            duration,
+            segments,
+            label: None,
+            note: None
+        })
+    }
+
+    /// When this lap ended. This is the end of the last segment, or `start`
+    /// plus `duration` if the lap has no segments (just `start` if that is
+    /// too late to be a [`SystemTime`]).
+    ///
+    /// Since [`FinishedLap::adjust`] leaves the segments as they are, adding
+    /// or taking away time doesn't move the end of a lap that has segments.
+    pub fn end_time(&self) -> SystemTime {
+        match self.segments.last() {
+            Some(segment) => segment.end,
+            None => self.start.checked_add(self.duration).unwrap_or(self.start)
+        }
+    }
+}
+
+/// Time to add to or take away from a lap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeAdjustment {
+    Add(Duration),
+    Subtract(Duration)
+}
+
+impl TimeAdjustment {
+    /// Adjust `duration`. Fails if the result would be negative or too large.
+    pub fn apply(self, duration: Duration) -> Result<Duration, Self> {
+        match self {
+            Self::Add(amount) => duration.checked_add(amount),
+            Self::Subtract(amount) => duration.checked_sub(amount)
+        }.ok_or(self)
+    }
+}
+
+impl Default for TimeAdjustment {
+    fn default() -> Self {
+        Self::Add(Duration::ZERO)
+    }
+}
+
+/// A period of time during which a lap was playing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: SystemTime,
+    pub end: SystemTime
+}
+
+impl Segment {
+    /// How long this segment lasted.
+    pub fn duration(&self) -> Duration {
+        self.end.duration_since(self.start).unwrap_or_default()
+    }
+}
+
+/// Changes to the label and note of a lap.
+///
+/// [`None`] leaves the field as it is, while an empty string removes it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LapAnnotation {
+    pub label: Option<String>,
+    pub note: Option<String>
+}
+
+impl LapAnnotation {
+    /// Whether this annotation changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none() && self.note.is_none()
+    }
+
+    fn apply(&self, label: &mut Option<String>, note: &mut Option<String>) {
+        for (new, old) in [(&self.label, label), (&self.note, note)] {
+            match new.as_deref() {
+                Some("") => *old = None,
+                Some(new) => *old = Some(new.to_string()),
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use crate::identifiers::UniqueId;
+
+    use crate::error::LapError;
+
+    use super::{CurrentLap, CurrentLapSnapshot, LapAnnotation, TimeAdjustment};
+
+    #[test]
+    fn test_snapshot_paused() {
+        let mut lap = CurrentLap::new(UniqueId::generate());
+        lap.duration = Duration::from_secs(5);
+        let snapshot: CurrentLapSnapshot = lap.clone().into();
+        assert_eq!(snapshot.playing_since, None);
+
+        let restored: CurrentLap = snapshot.into();
+        assert!(!restored.playing());
+        assert_eq!(restored.id, lap.id);
+        assert_eq!(restored.total_time(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_snapshot_playing() {
+        let lap = CurrentLap::start(UniqueId::generate());
+        let mut snapshot: CurrentLapSnapshot = lap.into();
+        // Pretend the snapshot was saved a minute ago
+        snapshot.playing_since = Some(SystemTime::now() - Duration::from_secs(60));
+
+        let restored: CurrentLap = snapshot.into();
+        assert!(restored.playing());
+        assert!(restored.total_time() >= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut lap = CurrentLap::new(UniqueId::generate());
+        lap.annotate(&LapAnnotation {
+            label: Some("compile".to_string()),
+            note: Some("first build".to_string())
+        });
+        lap.annotate(&LapAnnotation { label: None, note: Some(String::new()) });
+        let finished = lap.end();
+        assert_eq!(finished.label.as_deref(), Some("compile"));
+        assert_eq!(finished.note, None);
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut lap = CurrentLap::start(UniqueId::generate());
+        lap.pause();
+        lap.pause();
+        lap.play();
+        assert_eq!(lap.segments.len(), 1);
+        assert_eq!(lap.all_segments().len(), 2);
+
+        let finished = lap.end();
+        assert_eq!(finished.segments.len(), 2);
+        assert!(finished.segments[0].end <= finished.segments[1].start);
+        assert!(finished.segments.iter().all(|s| s.start <= s.end));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_segments() {
+        let mut lap = CurrentLap::start(UniqueId::generate());
+        lap.pause();
+        lap.play();
+        let ongoing_start = lap.ongoing_segment().unwrap().start;
+        let snapshot: CurrentLapSnapshot = lap.into();
+
+        let restored: CurrentLap = snapshot.into();
+        assert_eq!(restored.segments.len(), 1);
+        assert_eq!(restored.ongoing_segment().unwrap().start, ongoing_start);
+    }
+
+    #[test]
+    fn test_backdate() {
+        let now = SystemTime::now();
+        let mut lap = CurrentLap::start_at(UniqueId::generate(), now - Duration::from_secs(60))
+            .unwrap();
+        assert!(lap.total_time() >= Duration::from_secs(60));
+
+        lap.pause_at(now - Duration::from_secs(30)).unwrap();
+        // Instants and system times are read at slightly different moments
+        let error = lap.total_time().abs_diff(Duration::from_secs(30));
+        assert!(error < Duration::from_secs(1));
+        assert_eq!(lap.segments[0].duration(), Duration::from_secs(30));
+        // Already paused, so the time doesn't matter
+        assert_eq!(lap.pause_at(now - Duration::from_secs(45)), Ok(()));
+
+        let too_early = lap.play_at(now - Duration::from_secs(45));
+        assert!(matches!(too_early, Err(LapError::TooEarly { .. })));
+        let future = lap.play_at(SystemTime::now() + Duration::from_secs(60));
+        assert_eq!(future, Err(LapError::FutureTime));
+        assert!(!lap.playing());
+    }
+
+    #[test]
+    fn test_adjust() {
+        let mut lap = CurrentLap::new(UniqueId::generate());
+        lap.duration = Duration::from_secs(60);
+        lap.adjust(TimeAdjustment::Subtract(Duration::from_secs(45))).unwrap();
+        assert_eq!(lap.total_time(), Duration::from_secs(15));
+        let too_much = TimeAdjustment::Subtract(Duration::from_secs(20));
+        assert_eq!(lap.adjust(too_much), Err(too_much));
+        assert_eq!(lap.total_time(), Duration::from_secs(15));
+
+        let mut finished = lap.end();
+        finished.adjust(TimeAdjustment::Add(Duration::from_secs(5))).unwrap();
+        assert_eq!(finished.duration, Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_end_time_overflow() {
+        let mut lap = CurrentLap::new(UniqueId::generate());
+        lap.duration = Duration::MAX;
+        let mut finished = lap.end();
+        assert_eq!(finished.end_time(), finished.start);
+
+        let rest = finished.split(Duration::MAX - Duration::from_secs(1)).unwrap();
+        assert_eq!(rest.start, finished.start);
+        assert_eq!(rest.duration, Duration::from_secs(1));
+    }
 }
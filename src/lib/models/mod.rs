@@ -1,4 +1,10 @@
 //! Structs to represent important data.
 
+pub mod auto_lap;
 pub mod lap;
-pub mod stopwatch;
\ No newline at end of file
+pub mod pomodoro;
+pub mod program;
+pub mod run;
+pub mod snapshot;
+pub mod stopwatch;
+pub mod timer;
\ No newline at end of file
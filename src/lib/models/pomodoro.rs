@@ -0,0 +1,143 @@
+//! Pomodoro mode, where each lap of a [`Stopwatch`] is a work or break phase.
+//!
+//! A [`Stopwatch`] in pomodoro mode carries a [`Pomodoro`], which keeps track
+//! of the current [`Phase`] and how many work phases have been finished. Once
+//! the current lap has run for as long as its phase, `swd` ends the lap and
+//! starts the next phase with
+//! [`Stopwatch::check_pomodoro`](super::stopwatch::Stopwatch::check_pomodoro).
+
+use std::{fmt, time::Duration};
+
+use serde::{Serialize, Deserialize};
+
+#[allow(unused)]
+use super::stopwatch::Stopwatch; // for see also documentation
+
+/// Part of a pomodoro cycle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak
+}
+
+impl Phase {
+    pub fn is_break(&self) -> bool {
+        !matches!(self, Self::Work)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Phase::*;
+        write!(f, "{}", match self {
+            Work => "work",
+            ShortBreak => "short break",
+            LongBreak => "long break"
+        })
+    }
+}
+
+/// Lengths of each [`Phase`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PomodoroConfig {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    /// Take a long break instead of a short one after this many work phases,
+    /// or never if 0.
+    pub long_break_every: usize
+}
+
+impl PomodoroConfig {
+    /// Length of `phase`.
+    pub fn length(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break
+        }
+    }
+}
+
+impl Default for PomodoroConfig {
+    /// 25 minutes of work, 5 minute breaks and a 15 minute break every 4
+    /// work phases.
+    fn default() -> Self {
+        Self {
+            work: Duration::from_secs(25 * 60),
+            short_break: Duration::from_secs(5 * 60),
+            long_break: Duration::from_secs(15 * 60),
+            long_break_every: 4
+        }
+    }
+}
+
+/// Where a [`Stopwatch`] in pomodoro mode is in its cycle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pomodoro {
+    pub config: PomodoroConfig,
+    /// Phase of the current lap.
+    pub phase: Phase,
+    /// Number of work phases that have been finished.
+    pub cycles: usize
+}
+
+impl Pomodoro {
+    /// Start with a work phase.
+    pub fn new(config: PomodoroConfig) -> Self {
+        Self { config, phase: Phase::Work, cycles: 0 }
+    }
+
+    /// Length of the current phase.
+    pub fn phase_length(&self) -> Duration {
+        self.config.length(self.phase)
+    }
+
+    /// The phase after the current one.
+    pub fn next_phase(&self) -> Phase {
+        match self.phase {
+            Phase::Work if (self.cycles + 1).is_multiple_of(self.config.long_break_every) => {
+                Phase::LongBreak
+            },
+            Phase::Work => Phase::ShortBreak,
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work
+        }
+    }
+
+    /// Move on to the next phase and return it.
+    pub fn advance(&mut self) -> Phase {
+        let next = self.next_phase();
+        if self.phase == Phase::Work {
+            self.cycles += 1;
+        }
+        self.phase = next;
+        next
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Phase, Pomodoro, PomodoroConfig};
+
+    #[test]
+    fn test_advance() {
+        let config = PomodoroConfig { long_break_every: 2, ..Default::default() };
+        let mut pomodoro = Pomodoro::new(config);
+        let phases: Vec<Phase> = (0..6).map(|_| pomodoro.advance()).collect();
+        assert_eq!(phases, [
+            Phase::ShortBreak,
+            Phase::Work,
+            Phase::LongBreak,
+            Phase::Work,
+            Phase::ShortBreak,
+            Phase::Work
+        ]);
+        assert_eq!(pomodoro.cycles, 3);
+        assert_eq!(pomodoro.phase_length(), config.work);
+
+        // Never take a long break
+        let mut pomodoro = Pomodoro::new(PomodoroConfig { long_break_every: 0, ..config });
+        assert!((0..8).all(|_| pomodoro.advance() != Phase::LongBreak));
+    }
+}
@@ -0,0 +1,239 @@
+//! Interval programs, where each lap of a [`Stopwatch`] is a step with a set
+//! length, like "8 x (20s work, 10s rest)".
+//!
+//! Programs are written as a [`ProgramDefinition`], which is a list of
+//! [`StepSet`]s that can each be repeated. They are expanded into a flat
+//! [`Program`] with [`ProgramDefinition::expand`]. A [`Stopwatch`] running a
+//! program carries a [`ProgramProgress`], and `swd` ends each lap once its
+//! step has run out with
+//! [`Stopwatch::check_program`](super::stopwatch::Stopwatch::check_program).
+//!
+//! With the `programs` feature, definitions can be read from TOML using
+//! [`ProgramDefinition::from_toml`]:
+//!
+//! ```toml
+//! name = "tabata"
+//!
+//! [[sets]]
+//! repeat = 8
+//! steps = [
+//!     { name = "work", duration = "20s" },
+//!     { name = "rest", duration = "10s" },
+//! ]
+//!
+//! [[sets]]
+//! steps = [{ name = "cool down", duration = "5m" }]
+//! ```
+
+use std::{fmt, time::Duration};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{error::ProgramError, fmt::parse::parse_duration};
+#[allow(unused)]
+use super::stopwatch::Stopwatch; // for see also documentation
+
+/// One step of a [`Program`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub name: String,
+    pub duration: Duration
+}
+
+/// List of steps to be done one after another.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Program {
+    pub name: String,
+    pub steps: Vec<Step>
+}
+
+impl Program {
+    /// How long the whole program takes.
+    pub fn total_duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+}
+
+/// How a [`Program`] finished.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgramOutcome {
+    /// Every step was done.
+    Completed,
+    /// The stopwatch was stopped before the last step finished.
+    Aborted
+}
+
+impl fmt::Display for ProgramOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ProgramOutcome::*;
+        write!(f, "{}", match self {
+            Completed => "completed",
+            Aborted => "aborted"
+        })
+    }
+}
+
+/// Where a [`Stopwatch`] running a [`Program`] is.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramProgress {
+    pub program: Program,
+    /// Index of the step of the current lap.
+    pub step: usize,
+    /// [`Some`] once the program has finished.
+    pub outcome: Option<ProgramOutcome>
+}
+
+impl ProgramProgress {
+    pub fn new(program: Program) -> Self {
+        Self { program, step: 0, outcome: None }
+    }
+
+    /// The step being done. [`None`] if the program has finished.
+    pub fn current_step(&self) -> Option<&Step> {
+        match self.outcome {
+            Some(_) => None,
+            None => self.program.steps.get(self.step)
+        }
+    }
+
+    /// Whether the current step is the last one.
+    pub fn on_last_step(&self) -> bool {
+        self.step + 1 >= self.program.steps.len()
+    }
+
+    /// Number of steps after the current one.
+    pub fn remaining_steps(&self) -> usize {
+        match self.outcome {
+            Some(_) => 0,
+            None => self.program.steps.len().saturating_sub(self.step + 1)
+        }
+    }
+}
+
+/// Steps in a [`ProgramDefinition`] and how many times to do them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepSet {
+    #[serde(default = "default_repeat")]
+    pub repeat: usize,
+    pub steps: Vec<StepDefinition>
+}
+
+fn default_repeat() -> usize {
+    1
+}
+
+/// A step in a [`StepSet`], with its duration written like `20s` or `1:30`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepDefinition {
+    pub name: String,
+    pub duration: String
+}
+
+/// A [`Program`] as written by the user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramDefinition {
+    pub name: Option<String>,
+    pub sets: Vec<StepSet>
+}
+
+impl ProgramDefinition {
+    /// Read a definition from TOML. See the [module documentation](self) for
+    /// the format.
+    #[cfg(feature = "programs")]
+    pub fn from_toml(input: &str) -> Result<Self, ProgramError> {
+        toml::from_str(input).map_err(|e| ProgramError::Toml(e.to_string()))
+    }
+
+    /// Repeat each [`StepSet`] and join them into a [`Program`]. `name` is
+    /// used if the definition doesn't have a name.
+    pub fn expand(&self, name: &str) -> Result<Program, ProgramError> {
+        let mut steps = vec![];
+        for set in &self.sets {
+            let mut set_steps = Vec::with_capacity(set.steps.len());
+            for step in &set.steps {
+                let bad_step = |error: String| ProgramError::BadStep { step: step.name.clone(), error };
+                let duration = parse_duration(&step.duration).map_err(|e| bad_step(e.to_string()))?;
+                if duration.is_zero() {
+                    return Err(bad_step("duration must be more than 0".to_string()));
+                }
+                set_steps.push(Step { name: step.name.clone(), duration });
+            }
+            for _ in 0..set.repeat {
+                steps.extend(set_steps.iter().cloned());
+            }
+        }
+        if steps.is_empty() {
+            return Err(ProgramError::Empty);
+        }
+        let name = self.name.clone().unwrap_or_else(|| name.to_string());
+        Ok(Program { name, steps })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::error::ProgramError;
+
+    use super::{ProgramDefinition, ProgramOutcome, ProgramProgress, StepDefinition, StepSet};
+
+    fn step(name: &str, duration: &str) -> StepDefinition {
+        StepDefinition { name: name.to_string(), duration: duration.to_string() }
+    }
+
+    #[test]
+    fn test_expand() {
+        let definition = ProgramDefinition {
+            name: None,
+            sets: vec![
+                StepSet { repeat: 3, steps: vec![step("work", "20s"), step("rest", "10s")] },
+                StepSet { repeat: 1, steps: vec![step("cool down", "5m")] }
+            ]
+        };
+        let program = definition.expand("tabata").unwrap();
+        assert_eq!(program.name, "tabata");
+        assert_eq!(program.steps.len(), 7);
+        assert_eq!(program.steps[2].name, "work");
+        assert_eq!(program.total_duration(), Duration::from_secs(90 + 300));
+
+        let mut progress = ProgramProgress::new(program);
+        assert_eq!(progress.remaining_steps(), 6);
+        progress.step = 6;
+        assert!(progress.on_last_step());
+        assert_eq!(progress.current_step().unwrap().name, "cool down");
+        progress.outcome = Some(ProgramOutcome::Completed);
+        assert_eq!(progress.current_step(), None);
+
+        let single = |repeat, duration| ProgramDefinition {
+            name: None,
+            sets: vec![StepSet { repeat, steps: vec![step("a", duration)] }]
+        };
+        assert!(matches!(single(2, "0s").expand("bad"), Err(ProgramError::BadStep { .. })));
+        assert!(matches!(single(2, "soon").expand("bad"), Err(ProgramError::BadStep { .. })));
+        assert_eq!(single(0, "1s").expand("empty"), Err(ProgramError::Empty));
+    }
+
+    #[cfg(feature = "programs")]
+    #[test]
+    fn test_from_toml() {
+        let input = r#"
+            name = "tabata"
+
+            [[sets]]
+            repeat = 8
+            steps = [
+                { name = "work", duration = "20s" },
+                { name = "rest", duration = "10s" },
+            ]
+
+            [[sets]]
+            steps = [{ name = "cool down", duration = "5m" }]
+        "#;
+        let program = ProgramDefinition::from_toml(input).unwrap().expand("file").unwrap();
+        assert_eq!(program.name, "tabata");
+        assert_eq!(program.steps.len(), 17);
+        assert_eq!(program.steps[16].duration, Duration::from_secs(300));
+        assert!(ProgramDefinition::from_toml("sets = 1").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Runs, where each lap of a [`Stopwatch`] completes the next segment in a
+//! list, like a split timer for speedrunning.
+//!
+//! `swd` keeps a [`Run`] for each run name, which stores every attempt along
+//! with the personal best and the best time for each segment ("gold"). A
+//! [`Stopwatch`] doing a run carries a [`RunProgress`] so that the current
+//! attempt can be compared against them.
+
+use std::time::{Duration, SystemTime};
+
+use serde::{Serialize, Deserialize};
+
+#[allow(unused)]
+use super::stopwatch::Stopwatch; // for see also documentation
+
+/// A named part of a [`Run`] with the times to beat.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSegment {
+    pub name: String,
+    /// How long this segment took during the personal best.
+    pub personal_best: Option<Duration>,
+    /// Shortest time this segment has ever been done in.
+    pub gold: Option<Duration>
+}
+
+impl RunSegment {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self { name: name.into(), personal_best: None, gold: None }
+    }
+}
+
+/// One go at a [`Run`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunAttempt {
+    /// When the attempt started.
+    pub start: Option<SystemTime>,
+    /// How long each segment took. If the attempt was given up on, segments
+    /// that weren't finished are left out.
+    pub segments: Vec<Duration>
+}
+
+impl RunAttempt {
+    /// Time spent on all the finished segments.
+    pub fn total_time(&self) -> Duration {
+        self.segments.iter().sum()
+    }
+}
+
+/// List of segments and the history of attempts at them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Run {
+    pub name: String,
+    pub segments: Vec<RunSegment>,
+    /// Every attempt, oldest first.
+    pub attempts: Vec<RunAttempt>
+}
+
+impl Run {
+    pub fn new<S, I>(name: S, segments: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator,
+        I::Item: Into<String>
+    {
+        let segments = segments.into_iter().map(RunSegment::new).collect();
+        Self { name: name.into(), segments, attempts: vec![] }
+    }
+
+    /// Total time of the personal best, if the run has been finished before.
+    pub fn personal_best(&self) -> Option<Duration> {
+        self.segments.iter().map(|s| s.personal_best).sum()
+    }
+
+    /// Total of the gold times, if every segment has been finished before.
+    pub fn sum_of_best(&self) -> Option<Duration> {
+        self.segments.iter().map(|s| s.gold).sum()
+    }
+
+    /// Whether the segments of this run are called `names`, in order.
+    pub fn has_segments(&self, names: &[String]) -> bool {
+        self.segments.iter().map(|s| &s.name).eq(names)
+    }
+
+    /// Whether `attempt` finished every segment.
+    pub fn is_complete(&self, attempt: &RunAttempt) -> bool {
+        attempt.segments.len() >= self.segments.len()
+    }
+
+    /// Number of attempts that finished every segment.
+    pub fn completed_attempts(&self) -> usize {
+        self.attempts.iter().filter(|a| self.is_complete(a)).count()
+    }
+
+    /// Add an attempt to the history, updating the gold of each segment it
+    /// beat. Returns whether the attempt is a new personal best.
+    pub fn record(&mut self, attempt: RunAttempt) -> bool {
+        for (segment, time) in self.segments.iter_mut().zip(&attempt.segments) {
+            if !matches!(segment.gold, Some(gold) if gold <= *time) {
+                segment.gold = Some(*time);
+            }
+        }
+        let new_pb = self.is_complete(&attempt)
+            && !matches!(self.personal_best(), Some(pb) if pb <= attempt.total_time());
+        if new_pb {
+            for (segment, time) in self.segments.iter_mut().zip(&attempt.segments) {
+                segment.personal_best = Some(*time);
+            }
+        }
+        self.attempts.push(attempt);
+        new_pb
+    }
+}
+
+/// The [`Run`] a [`Stopwatch`] is doing, with the times it is compared to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunProgress {
+    /// Name of the [`Run`].
+    pub name: String,
+    pub segments: Vec<RunSegment>
+}
+
+impl From<&Run> for RunProgress {
+    fn from(run: &Run) -> Self {
+        Self { name: run.name.clone(), segments: run.segments.clone() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Run, RunAttempt};
+
+    fn attempt(secs: &[u64]) -> RunAttempt {
+        RunAttempt { start: None, segments: secs.iter().map(|s| Duration::from_secs(*s)).collect() }
+    }
+
+    #[test]
+    fn test_record() {
+        let secs = Duration::from_secs;
+        let mut run = Run::new("any%", ["first", "second", "third"]);
+        assert_eq!(run.personal_best(), None);
+
+        // Given up on, but still sets golds
+        assert!(!run.record(attempt(&[10, 20])));
+        assert_eq!(run.personal_best(), None);
+        assert_eq!(run.segments[1].gold, Some(secs(20)));
+
+        assert!(run.record(attempt(&[12, 18, 30])));
+        assert_eq!(run.personal_best(), Some(secs(60)));
+        assert_eq!(run.sum_of_best(), Some(secs(58)));
+
+        assert!(!run.record(attempt(&[9, 25, 30])));
+        assert_eq!(run.segments[0].personal_best, Some(secs(12)));
+        assert_eq!(run.segments[0].gold, Some(secs(9)));
+        assert_eq!(run.sum_of_best(), Some(secs(57)));
+
+        assert!(run.record(attempt(&[11, 19, 29])));
+        assert_eq!(run.personal_best(), Some(secs(59)));
+        assert_eq!(run.attempts.len(), 4);
+        assert_eq!(run.completed_attempts(), 3);
+    }
+
+    #[test]
+    fn test_has_segments() {
+        let run = Run::new("any%", ["first", "second", "third"]);
+        assert!(run.has_segments(&["first".to_string(), "second".to_string(), "third".to_string()]));
+        assert!(!run.has_segments(&["first".to_string()]));
+    }
+}
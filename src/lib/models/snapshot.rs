@@ -0,0 +1,343 @@
+//! Serializable snapshots of models.
+//!
+//! A playing [`Stopwatch`] keeps time using an [`Instant`](std::time::Instant),
+//! which means it can't be written to disk or sent elsewhere as is. Instead,
+//! take a [`StopwatchSnapshot`] with [`Stopwatch::snapshot`] and turn it back
+//! into a [`Stopwatch`] later with [`StopwatchSnapshot::restore`].
+//!
+//! [`Timer`]s are snapshotted the same way using [`TimerSnapshot`].
+//!
+//! Every snapshot records the [`SCHEMA_VERSION`] it was made with. When
+//! snapshots made by older versions of Stopwatchd are read with
+//! [`StopwatchSnapshot::from_value`] or [`StopwatchSnapshot::decode`], they
+//! are upgraded to the current schema using [`MIGRATIONS`] first.
+
+use std::{collections::BTreeSet, time::{Duration, SystemTime}};
+
+use ciborium::{de::from_reader, ser::into_writer, value::{Integer, Value}};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use uuid::Uuid;
+
+use crate::{error::SnapshotError, identifiers::{Identifier, UniqueId, Tag}};
+
+use super::{
+    auto_lap::AutoLap,
+    lap::{FinishedLap, Segment},
+    pomodoro::Pomodoro,
+    program::ProgramProgress,
+    run::RunProgress,
+    stopwatch::{ArchivedRun, Goals, Stopwatch},
+    timer::{Timer, TimerOutcome}
+};
+
+/// Current version of the snapshot schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the field storing the schema version of a snapshot.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades a snapshot (as a CBOR map) from one schema version to the next.
+///
+/// The migration doesn't have to update [`SCHEMA_VERSION_KEY`], that is done
+/// by [`migrate`].
+pub type Migration = fn(&mut Vec<(Value, Value)>) -> Result<(), SnapshotError>;
+
+/// `MIGRATIONS[n]` upgrades a snapshot from version `n` to version `n + 1`.
+pub const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    migrate_v0_to_v1
+];
+
+/// Version 0 is the unversioned format `swd` used to save its state with.
+/// Stopwatches only had their identifier and laps, and laps only had their
+/// times, so everything else is filled in with defaults.
+fn migrate_v0_to_v1(snapshot: &mut Vec<(Value, Value)>) -> Result<(), SnapshotError> {
+    for lap in lap_maps(snapshot) {
+        add_field(lap, "label", None::<String>)?;
+        add_field(lap, "note", None::<String>)?;
+        add_field(lap, "segment_start", None::<SystemTime>)?;
+        add_field(lap, "segments", Vec::<Segment>::new())?;
+    }
+    if is_stopwatch(snapshot) {
+        add_field(snapshot, "tags", BTreeSet::<Tag>::new())?;
+        add_field(snapshot, "archived_runs", Vec::<ArchivedRun>::new())?;
+        add_field(snapshot, "run", None::<RunProgress>)?;
+        add_field(snapshot, "goals", Goals::default())?;
+        add_field(snapshot, "pomodoro", None::<Pomodoro>)?;
+        add_field(snapshot, "program", None::<ProgramProgress>)?;
+        add_field(snapshot, "auto_lap", None::<AutoLap>)?;
+    }
+    Ok(())
+}
+
+/// Add `key` to `map` as `value` if `map` doesn't have it yet.
+fn add_field<T: Serialize>(
+    map: &mut Vec<(Value, Value)>,
+    key: &str,
+    value: T
+) -> Result<(), SnapshotError> {
+    if map.iter().all(|(k, _)| k.as_text() != Some(key)) {
+        let value = Value::serialized(&value)
+            .map_err(|e| SnapshotError::Malformed(e.to_string()))?;
+        map.push((Value::Text(key.to_string()), value));
+    }
+    Ok(())
+}
+
+/// Whether `snapshot` is of a stopwatch rather than a timer.
+fn is_stopwatch(snapshot: &[(Value, Value)]) -> bool {
+    snapshot.iter().any(|(k, _)| k.as_text() == Some("finished_laps"))
+}
+
+/// Maps of every lap in a stopwatch or timer snapshot.
+fn lap_maps(snapshot: &mut [(Value, Value)]) -> Vec<&mut Vec<(Value, Value)>> {
+    let mut laps = vec![];
+    for (key, value) in snapshot.iter_mut() {
+        match (key.as_text(), value) {
+            (Some("current_lap" | "lap"), Value::Map(lap)) => laps.push(lap),
+            (Some("finished_laps"), Value::Array(finished)) => {
+                laps.extend(finished.iter_mut().filter_map(|lap| match lap {
+                    Value::Map(lap) => Some(lap),
+                    _ => None
+                }));
+            },
+            _ => {}
+        }
+    }
+    laps
+}
+
+/// Get the schema version of a snapshot. Snapshots without a version are
+/// assumed to be version 0.
+pub fn schema_version(snapshot: &[(Value, Value)]) -> Result<u32, SnapshotError> {
+    let version = snapshot.iter()
+        .find(|(k, _)| k.as_text() == Some(SCHEMA_VERSION_KEY))
+        .map(|(_, v)| v);
+    match version {
+        None => Ok(0),
+        Some(Value::Integer(i)) => u32::try_from(*i)
+            .map_err(|_| SnapshotError::Malformed(format!("bad schema version: {:?}", i))),
+        Some(v) => Err(SnapshotError::Malformed(format!("bad schema version: {:?}", v)))
+    }
+}
+
+fn set_schema_version(snapshot: &mut Vec<(Value, Value)>, version: u32) {
+    let version = Value::Integer(Integer::from(version));
+    match snapshot.iter_mut().find(|(k, _)| k.as_text() == Some(SCHEMA_VERSION_KEY)) {
+        Some((_, v)) => *v = version,
+        None => snapshot.push((Value::Text(SCHEMA_VERSION_KEY.to_string()), version))
+    }
+}
+
+/// Upgrade a snapshot to [`SCHEMA_VERSION`].
+pub fn migrate(snapshot: Value) -> Result<Value, SnapshotError> {
+    let mut snapshot = snapshot.into_map()
+        .map_err(|_| SnapshotError::Malformed("snapshot is not a map".to_string()))?;
+    let mut version = schema_version(&snapshot)?;
+    if version > SCHEMA_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    while version < SCHEMA_VERSION {
+        MIGRATIONS[version as usize](&mut snapshot)?;
+        version += 1;
+        set_schema_version(&mut snapshot, version);
+    }
+    Ok(Value::Map(snapshot))
+}
+
+/// Migrate `value` and then deserialize it into a snapshot.
+fn read_snapshot<T: DeserializeOwned>(value: Value) -> Result<T, SnapshotError> {
+    let value = migrate(value)?;
+    // Going through bytes again so that types which serialize differently
+    // in compact formats (like Uuid) are read the same way they're written
+    let mut buffer = vec![];
+    into_writer(&value, &mut buffer)
+        .map_err(|e| SnapshotError::Malformed(e.to_string()))?;
+    from_reader(buffer.as_slice())
+        .map_err(|e| SnapshotError::Malformed(e.to_string()))
+}
+
+fn decode_snapshot<T: DeserializeOwned>(buffer: &[u8]) -> Result<T, SnapshotError> {
+    let value: Value = from_reader(buffer)
+        .map_err(|e| SnapshotError::Malformed(e.to_string()))?;
+    read_snapshot(value)
+}
+
+/// Serializable form of a [`Stopwatch`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopwatchSnapshot {
+    pub schema_version: u32,
+    pub identifier: Identifier,
+    pub tags: BTreeSet<Tag>,
+    pub finished_laps: Vec<FinishedLap>,
+    pub current_lap: Option<CurrentLapSnapshot>,
+    pub archived_runs: Vec<ArchivedRun>,
+    pub run: Option<RunProgress>,
+    pub goals: Goals,
+    pub pomodoro: Option<Pomodoro>,
+    pub program: Option<ProgramProgress>,
+    pub auto_lap: Option<AutoLap>
+}
+
+impl StopwatchSnapshot {
+    /// Read a snapshot of any supported schema version.
+    pub fn from_value(value: Value) -> Result<Self, SnapshotError> {
+        read_snapshot(value)
+    }
+
+    /// Decode a snapshot of any supported schema version from CBOR bytes.
+    ///
+    /// Use [`Codecable::to_bytes`](crate::traits::Codecable::to_bytes) to
+    /// encode a snapshot.
+    pub fn decode(buffer: &[u8]) -> Result<Self, SnapshotError> {
+        decode_snapshot(buffer)
+    }
+
+    /// Rebuild the [`Stopwatch`]. If the stopwatch was playing when the
+    /// snapshot was taken, the time since then is counted too.
+    pub fn restore(self) -> Stopwatch {
+        self.into()
+    }
+}
+
+/// Serializable form of a [`CurrentLap`](super::lap::CurrentLap).
+///
+/// If the lap is playing, `duration` includes all the time the lap has been
+/// running up to `playing_since`, which is the wall-clock time the snapshot
+/// was taken. When the snapshot is turned back into a
+/// [`CurrentLap`](super::lap::CurrentLap), the time that has passed since
+/// `playing_since` is added to the lap.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentLapSnapshot {
+    pub id: Uuid,
+    pub sw_id: UniqueId,
+    pub start: SystemTime,
+    pub duration: Duration,
+    pub playing_since: Option<SystemTime>,
+    /// When the ongoing segment started, if the lap is playing.
+    pub segment_start: Option<SystemTime>,
+    pub segments: Vec<Segment>,
+    pub label: Option<String>,
+    pub note: Option<String>
+}
+
+/// Serializable form of a [`Timer`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub schema_version: u32,
+    pub identifier: Identifier,
+    pub target: Duration,
+    pub lap: CurrentLapSnapshot,
+    pub outcome: Option<TimerOutcome>,
+    pub ended_at: Option<SystemTime>
+}
+
+impl TimerSnapshot {
+    /// Read a snapshot of any supported schema version.
+    pub fn from_value(value: Value) -> Result<Self, SnapshotError> {
+        read_snapshot(value)
+    }
+
+    /// Decode a snapshot of any supported schema version from CBOR bytes.
+    pub fn decode(buffer: &[u8]) -> Result<Self, SnapshotError> {
+        decode_snapshot(buffer)
+    }
+
+    /// Rebuild the [`Timer`]. If the timer was playing when the snapshot was
+    /// taken, the time since then is counted too.
+    pub fn restore(self) -> Timer {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use ciborium::value::Value;
+
+    use crate::{
+        error::SnapshotError,
+        identifiers::Name,
+        models::stopwatch::{State, Stopwatch},
+        traits::Codecable
+    };
+
+    use super::{SCHEMA_VERSION, SCHEMA_VERSION_KEY, StopwatchSnapshot};
+
+    /// Fields of stopwatches that unversioned snapshots don't have.
+    const FIELDS_SINCE_V0: &[&str] = &[
+        "tags", "archived_runs", "run", "goals", "pomodoro", "program", "auto_lap"
+    ];
+
+    /// Fields of laps that unversioned snapshots don't have.
+    const LAP_FIELDS_SINCE_V0: &[&str] = &["label", "note", "segment_start", "segments"];
+
+    fn make_stopwatch() -> Stopwatch {
+        let mut stopwatch = Stopwatch::start(Name::fixed("snap"));
+        stopwatch.new_lap(true);
+        stopwatch
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let stopwatch = make_stopwatch();
+        let snapshot = stopwatch.snapshot();
+        assert_eq!(snapshot.schema_version, SCHEMA_VERSION);
+
+        let bytes = snapshot.to_bytes().unwrap();
+        let decoded = StopwatchSnapshot::decode(&bytes).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        let restored = decoded.restore();
+        assert_eq!(restored.identifier, stopwatch.identifier);
+        assert_eq!(restored.state(), State::Playing);
+        assert_eq!(restored.laps(), 2);
+        assert_eq!(restored.finished_laps(), stopwatch.finished_laps());
+        assert!(restored.total_time() >= snapshot.current_lap.unwrap().duration);
+    }
+
+    #[test]
+    fn test_migrate_unversioned() {
+        let mut stopwatch = make_stopwatch();
+        stopwatch.pause();
+        let bytes = stopwatch.snapshot().to_bytes().unwrap();
+        let value: Value = ciborium::de::from_reader(bytes.as_slice()).unwrap();
+        // Turn the snapshot back into what swd saved before snapshots had a schema
+        let mut map = value.into_map().unwrap();
+        let strip_lap = |lap: &mut Vec<(Value, Value)>| {
+            lap.retain(|(k, _)| !LAP_FIELDS_SINCE_V0.contains(&k.as_text().unwrap()));
+        };
+        map.retain(|(k, _)| {
+            let key = k.as_text().unwrap();
+            key != SCHEMA_VERSION_KEY && !FIELDS_SINCE_V0.contains(&key)
+        });
+        for (key, value) in map.iter_mut() {
+            match (key.as_text(), value) {
+                (Some("current_lap"), Value::Map(lap)) => strip_lap(lap),
+                (Some("finished_laps"), Value::Array(laps)) => {
+                    laps.iter_mut().for_each(|lap| strip_lap(lap.as_map_mut().unwrap()));
+                },
+                _ => {}
+            }
+        }
+
+        // Fails if a field was added without a default in the migration
+        let migrated = StopwatchSnapshot::from_value(Value::Map(map)).unwrap();
+        assert_eq!(migrated.schema_version, SCHEMA_VERSION);
+        let restored = migrated.restore();
+        assert_eq!(restored.identifier, stopwatch.identifier);
+        assert_eq!(restored.state(), State::Paused);
+        assert_eq!(restored.total_time(), stopwatch.total_time());
+    }
+
+    #[test]
+    fn test_reject_newer_version() {
+        let mut snapshot = make_stopwatch().snapshot();
+        snapshot.schema_version = SCHEMA_VERSION + 1;
+        let bytes = snapshot.to_bytes().unwrap();
+        assert_eq!(
+            StopwatchSnapshot::decode(&bytes),
+            Err(SnapshotError::UnsupportedVersion(SCHEMA_VERSION + 1))
+        );
+    }
+}
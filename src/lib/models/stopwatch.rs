@@ -1,15 +1,25 @@
 //! Stopwatch.
 
 use std::{
+    collections::BTreeSet,
     time::{Duration, SystemTime},
     fmt
 };
 
 use serde::{Serialize, Deserialize};
 
-use crate::identifiers::{Identifier, UniqueId, Name};
+use uuid::Uuid;
 
-use super::lap::{CurrentLap, FinishedLap};
+use crate::{error::LapError, identifiers::{Identifier, LapRef, UniqueId, Name, Tag}};
+
+use super::{
+    auto_lap::AutoLap,
+    lap::{CurrentLap, FinishedLap, LapAnnotation, Segment, TimeAdjustment},
+    pomodoro::{Phase, Pomodoro, PomodoroConfig},
+    program::{Program, ProgramOutcome, ProgramProgress},
+    run::{RunAttempt, RunProgress},
+    snapshot::{SCHEMA_VERSION, StopwatchSnapshot}
+};
 
 /// Minimum default capacity for lists storing laps.
 pub const MIN_LAPS_CAPACITY: usize = 4;
@@ -47,12 +57,57 @@ impl fmt::Display for State {
     }
 }
 
+/// Laps of a [`Stopwatch`] from before it was reset.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedRun {
+    /// When the stopwatch was reset.
+    pub reset_at: SystemTime,
+    pub laps: Vec<FinishedLap>
+}
+
+impl ArchivedRun {
+    /// How much time the stopwatch was playing during this run.
+    pub fn total_time(&self) -> Duration {
+        self.laps.iter().map(|lap| lap.duration).sum()
+    }
+}
+
+/// How long a [`Stopwatch`] or each of its laps should take.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goals {
+    /// Goal for the total time of the stopwatch.
+    pub total: Option<Duration>,
+    /// Goal for the time of each lap.
+    pub lap: Option<Duration>
+}
+
+impl Goals {
+    /// Whether there are no goals.
+    pub fn is_empty(&self) -> bool {
+        self.total.is_none() && self.lap.is_none()
+    }
+}
+
 /// Represents a stopwatch, with laps and an API to pause and play.
-#[derive(Debug)]
+///
+/// See [`StopwatchSnapshot`] to serialize a stopwatch.
+#[derive(Clone, Debug)]
 pub struct Stopwatch {
     pub identifier: Identifier,
+    /// Tags used to group stopwatches.
+    pub tags: BTreeSet<Tag>,
     finished_laps: Vec<FinishedLap>,
-    current_lap: Option<CurrentLap> // If some, not yet ended
+    current_lap: Option<CurrentLap>, // If some, not yet ended
+    archived_runs: Vec<ArchivedRun>,
+    /// If [`Some`], each lap completes the next segment of this run.
+    pub run: Option<RunProgress>,
+    pub goals: Goals,
+    /// If [`Some`], each lap is a phase of a pomodoro.
+    pub pomodoro: Option<Pomodoro>,
+    /// If [`Some`], each lap is a step of an interval program.
+    pub program: Option<ProgramProgress>,
+    /// If [`Some`], laps are ended by themselves according to this policy.
+    pub auto_lap: Option<AutoLap>
 }
 
 impl Stopwatch {
@@ -62,7 +117,19 @@ impl Stopwatch {
         let identifier = Identifier::new(id, name.into());
         let finished_laps = Vec::new();
         let current_lap = Some(CurrentLap::new(id));
-        Self { identifier, finished_laps, current_lap }
+        let archived_runs = Vec::new();
+        Self {
+            identifier,
+            tags: BTreeSet::new(),
+            finished_laps,
+            current_lap,
+            archived_runs,
+            run: None,
+            goals: Goals::default(),
+            pomodoro: None,
+            program: None,
+            auto_lap: None
+        }
     }
 
     /// New stopwatch but start immediately.
@@ -72,6 +139,33 @@ impl Stopwatch {
         sw
     }
 
+    /// New stopwatch that started playing at `at`.
+    pub fn start_at<N: Into<Name>>(name: N, at: SystemTime) -> Result<Self, LapError> {
+        let mut sw = Self::new(name);
+        sw.current_lap = Some(CurrentLap::start_at(sw.identifier.id, at)?);
+        Ok(sw)
+    }
+
+    /// Stopwatch that has already ended, made from laps that were timed
+    /// somewhere else. The laps follow one after another from `start`.
+    pub fn from_laps<N: Into<Name>>(
+        name: N,
+        start: SystemTime,
+        laps: &[(Duration, LapAnnotation)]
+    ) -> Result<Self, LapError> {
+        if laps.is_empty() {
+            return Err(LapError::NoSuchLap { lap: 1, laps_count: 0 });
+        }
+        let mut sw = Self::new(name);
+        sw.current_lap = None;
+        let mut lap_start = start;
+        for (duration, annotation) in laps {
+            sw.insert_lap(lap_start, *duration, annotation)?;
+            lap_start = lap_start.checked_add(*duration).ok_or(LapError::FutureTime)?;
+        }
+        Ok(sw)
+    }
+
     /// Starts the stopwatch.
     pub fn play(&mut self) -> State {
         if let Some(ref mut lap) = self.current_lap {
@@ -94,8 +188,79 @@ impl Stopwatch {
         }
     }
 
+    /// Like [`Stopwatch::play`] but as if it happened at `at`.
+    pub fn play_at(&mut self, at: SystemTime) -> Result<State, LapError> {
+        match self.current_lap {
+            Some(ref mut lap) => {
+                let state = lap.state();
+                lap.play_at(at)?;
+                Ok(state)
+            },
+            None => Ok(State::Ended)
+        }
+    }
+
+    /// Like [`Stopwatch::pause`] but as if it happened at `at`.
+    pub fn pause_at(&mut self, at: SystemTime) -> Result<State, LapError> {
+        match self.current_lap {
+            Some(ref mut lap) => {
+                let state = lap.state();
+                lap.pause_at(at)?;
+                Ok(state)
+            },
+            None => Ok(State::Ended)
+        }
+    }
+
+    /// Like [`Stopwatch::new_lap`] but the current lap ends and the new lap
+    /// starts at `at`. The new lap only plays if the current lap was playing.
+    /// Returns the state the stopwatch was in.
+    pub fn new_lap_at(&mut self, at: SystemTime) -> Result<State, LapError> {
+        let state = match self.current_lap {
+            Some(ref mut lap) if lap.playing() => {
+                lap.pause_at(at)?;
+                State::Playing
+            },
+            Some(ref lap) => {
+                lap.check_time(at)?;
+                State::Paused
+            },
+            None => return Ok(State::Ended)
+        };
+        if self.on_last_segment() || self.on_last_step() {
+            self.complete_program();
+            self.end();
+            return Ok(state);
+        }
+        let next_lap = match state {
+            State::Playing => CurrentLap::start_at(self.identifier.id, at)?,
+            _ => CurrentLap::new_at(self.identifier.id, at)
+        };
+        let prev_lap = self.current_lap.replace(next_lap).unwrap();
+        self.finished_laps.push(prev_lap.end());
+        self.advance_phase();
+        Ok(state)
+    }
+
+    /// Like [`Stopwatch::end`] but as if it happened at `at`.
+    pub fn end_at(&mut self, at: SystemTime) -> Result<State, LapError> {
+        let state = self.pause_at(at)?;
+        self.end();
+        Ok(state)
+    }
+
     /// Stop the current lap and create a new lap.
+    ///
+    /// If the stopwatch is doing a run and the current lap is the last
+    /// segment, the stopwatch ends instead and the state it was in is
+    /// returned. The same goes for the last step of an interval program,
+    /// which completes the program. In pomodoro mode, the new lap is the next
+    /// phase.
     pub fn new_lap(&mut self, start_immediately: bool) -> State {
+        if self.on_last_segment() || self.on_last_step() {
+            self.complete_program();
+            return self.end();
+        }
         match self.current_lap.take() {
             Some(prev_lap) => {
                 if start_immediately {
@@ -104,6 +269,7 @@ impl Stopwatch {
                     self.current_lap = Some(CurrentLap::new(self.identifier.id));
                 }
                 self.finished_laps.push(prev_lap.end());
+                self.advance_phase();
                 if start_immediately {
                     State::Playing
                 } else {
@@ -114,6 +280,168 @@ impl Stopwatch {
         }
     }
 
+    /// Whether the current lap is the last segment of the run.
+    fn on_last_segment(&self) -> bool {
+        match self.run {
+            Some(ref run) => self.current_lap.is_some()
+                && self.finished_laps.len() + 1 >= run.segments.len(),
+            None => false
+        }
+    }
+
+    /// Whether the current lap is the last step of an interval program that
+    /// hasn't finished.
+    fn on_last_step(&self) -> bool {
+        match self.program {
+            Some(ref program) => self.current_lap.is_some()
+                && program.outcome.is_none()
+                && program.on_last_step(),
+            None => false
+        }
+    }
+
+    /// The attempt at the run so far, counting only laps that have finished.
+    /// [`None`] if the stopwatch isn't doing a run.
+    pub fn run_attempt(&self) -> Option<RunAttempt> {
+        let run = self.run.as_ref()?;
+        let segments = self.finished_laps.iter()
+            .take(run.segments.len())
+            .map(|lap| lap.duration)
+            .collect();
+        Some(RunAttempt { start: self.start_time(), segments })
+    }
+
+    /// Put the stopwatch in pomodoro mode, starting from a work phase in the
+    /// current lap.
+    pub fn start_pomodoro(&mut self, config: PomodoroConfig) {
+        self.pomodoro = Some(Pomodoro::new(config));
+        self.label_phase();
+    }
+
+    /// End the current phase of a stopwatch in pomodoro mode and start the
+    /// next one in a new lap. The new lap plays if the stopwatch was playing.
+    /// Returns the new phase, or [`None`] if the stopwatch isn't in pomodoro
+    /// mode or has ended.
+    pub fn next_phase(&mut self) -> Option<Phase> {
+        let playing = match (self.pomodoro, self.current_lap.as_ref()) {
+            (Some(_), Some(lap)) => lap.playing(),
+            _ => return None
+        };
+        self.new_lap(playing);
+        self.pomodoro.map(|pomodoro| pomodoro.phase)
+    }
+
+    /// Start the next phase for every phase of the pomodoro that has run
+    /// out. Each phase starts right when the previous one ran out, so no time
+    /// is lost between checks. Returns how many phases were finished.
+    pub fn check_pomodoro(&mut self) -> usize {
+        self.lap_when_due(|sw| sw.pomodoro.map(|pomodoro| pomodoro.phase_length()))
+    }
+
+    /// Start a program, which begins with its first step in the current lap.
+    pub fn start_program(&mut self, program: Program) {
+        self.program = Some(ProgramProgress::new(program));
+        self.label_phase();
+    }
+
+    /// End the current step of a program early and start the next one. The
+    /// new lap plays if the stopwatch was playing, and skipping the last step
+    /// completes the program. Returns [`false`] if the stopwatch isn't
+    /// running a program or has ended.
+    pub fn next_step(&mut self) -> bool {
+        let playing = match (self.program.as_ref(), self.current_lap.as_ref()) {
+            (Some(program), Some(lap)) if program.outcome.is_none() => lap.playing(),
+            _ => return false
+        };
+        self.new_lap(playing);
+        true
+    }
+
+    /// Start the next step for every step of the program that has run out,
+    /// like [`Stopwatch::check_pomodoro`]. The stopwatch ends once the last
+    /// step has run out. Returns how many steps were finished.
+    pub fn check_program(&mut self) -> usize {
+        self.lap_when_due(|sw| sw.program.as_ref()?.current_step().map(|step| step.duration))
+    }
+
+    /// Start a new lap for every lap that should have ended according to the
+    /// [`AutoLap`] policy. Laps end right when they were due, unless the
+    /// stopwatch was paused at that time, in which case the lap ends when it
+    /// was played again. Returns how many laps were ended.
+    pub fn check_auto_lap(&mut self) -> usize {
+        let mut finished = self.lap_when_due(|sw| sw.auto_lap?.interval());
+        while let (Some(auto_lap), Some(lap)) = (self.auto_lap, self.current_lap.as_ref()) {
+            let boundary = match auto_lap.next_boundary(lap.start) {
+                Some(boundary) if lap.playing() && boundary <= SystemTime::now() => boundary,
+                _ => break
+            };
+            if self.new_lap_at(boundary.max(lap.last_change())).is_err() {
+                self.new_lap(true);
+            }
+            finished += 1;
+        }
+        finished
+    }
+
+    /// End the current lap each time it has played for as long as `length`
+    /// says, as if it ended right when the time ran out. Returns how many laps
+    /// were ended.
+    fn lap_when_due(&mut self, length: fn(&Self) -> Option<Duration>) -> usize {
+        let mut finished = 0;
+        while let (Some(length), Some(lap)) = (length(self), self.current_lap.as_ref()) {
+            let elapsed = lap.total_time();
+            if !lap.playing() || length.is_zero() || elapsed < length {
+                break;
+            }
+            let ended = SystemTime::now().checked_sub(elapsed - length)
+                .map(|at| self.new_lap_at(at));
+            if !matches!(ended, Some(Ok(_))) {
+                self.new_lap(true);
+            }
+            finished += 1;
+        }
+        finished
+    }
+
+    /// Move a pomodoro or program on to the next phase or step after a new
+    /// lap has started.
+    fn advance_phase(&mut self) {
+        if let Some(ref mut pomodoro) = self.pomodoro {
+            pomodoro.advance();
+        }
+        if let Some(ref mut program) = self.program {
+            if program.outcome.is_none() {
+                program.step += 1;
+            }
+        }
+        self.label_phase();
+    }
+
+    /// Label the current lap with the phase of the pomodoro or the step of
+    /// the program.
+    fn label_phase(&mut self) {
+        let label = match (self.pomodoro, self.program.as_ref()) {
+            (Some(pomodoro), _) => pomodoro.phase.to_string(),
+            (None, Some(program)) => match program.current_step() {
+                Some(step) => step.name.clone(),
+                None => return
+            },
+            (None, None) => return
+        };
+        if let Some(ref mut lap) = self.current_lap {
+            lap.label = Some(label);
+        }
+    }
+
+    /// Mark the program as completed.
+    fn complete_program(&mut self) {
+        if let Some(ref mut program) = self.program {
+            if program.outcome.is_none() {
+                program.outcome = Some(ProgramOutcome::Completed);
+            }
+        }
+    }
+
     /// Count the number of laps, including the current one.
     pub fn laps(&self) -> usize {
         self.finished_laps.len() + if self.current_lap.is_some() { 1 } else { 0 }
@@ -155,9 +483,204 @@ impl Stopwatch {
         laps
     }
 
-    /// Terminate this stopwatch such that it cannot be played again.
+    /// Change the label and note of a lap. `index` starts from 0 and
+    /// includes the current lap.
+    pub fn annotate_lap(
+        &mut self,
+        index: usize,
+        annotation: &LapAnnotation
+    ) -> Result<(), LapError> {
+        let laps_count = self.laps();
+        if let Some(lap) = self.finished_laps.get_mut(index) {
+            lap.annotate(annotation);
+            return Ok(());
+        }
+        match self.current_lap {
+            Some(ref mut lap) if index + 1 == laps_count => {
+                lap.annotate(annotation);
+                Ok(())
+            },
+            _ => Err(LapError::NoSuchLap { lap: index + 1, laps_count })
+        }
+    }
+
+    /// Add or take away time from a lap. `index` starts from 0 and includes
+    /// the current lap. A lap cannot be made longer than the time since it
+    /// started.
+    pub fn adjust_lap(
+        &mut self,
+        index: usize,
+        adjustment: TimeAdjustment
+    ) -> Result<(), LapError> {
+        let bad_adjustment = LapError::BadAdjustment { lap: index + 1, adjustment };
+        let (start, duration) = self.lap_start_and_duration(index)?;
+        // The total time of the stopwatch has to stay representable too
+        if let TimeAdjustment::Add(amount) = adjustment {
+            let end = adjustment.apply(duration).ok().and_then(|d| start.checked_add(d));
+            let too_long = !matches!(end, Some(end) if end <= SystemTime::now());
+            if !amount.is_zero() && (too_long || self.total_time().checked_add(amount).is_none()) {
+                return Err(bad_adjustment);
+            }
+        }
+        let result = match self.finished_laps.get_mut(index) {
+            Some(lap) => lap.adjust(adjustment),
+            None => match self.current_lap {
+                Some(ref mut lap) => lap.adjust(adjustment),
+                None => unreachable!("lap {} was found", index + 1)
+            }
+        };
+        result.map_err(|_| bad_adjustment)
+    }
+
+    /// When a lap started and how long it has been playing for. `index`
+    /// starts from 0 and includes the current lap.
+    fn lap_start_and_duration(&self, index: usize) -> Result<(SystemTime, Duration), LapError> {
+        match self.finished_laps.get(index) {
+            Some(lap) => Ok((lap.start, lap.duration)),
+            None => match self.current_lap {
+                Some(ref lap) if index == self.finished_laps.len() => {
+                    Ok((lap.start, lap.total_time()))
+                },
+                _ => Err(LapError::NoSuchLap { lap: index + 1, laps_count: self.laps() })
+            }
+        }
+    }
+
+    /// Add a lap that was timed somewhere else, starting at `start` and
+    /// lasting `duration`. The lap is put in between the other laps so that
+    /// they stay in order, and cannot overlap with any of them. If the
+    /// stopwatch hasn't ended, the lap has to finish before the current lap
+    /// started.
+    ///
+    /// Returns the index of the new lap.
+    pub fn insert_lap(
+        &mut self,
+        start: SystemTime,
+        duration: Duration,
+        annotation: &LapAnnotation
+    ) -> Result<usize, LapError> {
+        // A lap that ends too far ahead to represent ends in the future too
+        let end = start.checked_add(duration).ok_or(LapError::FutureTime)?;
+        if end > SystemTime::now() {
+            return Err(LapError::FutureTime);
+        }
+        let index = self.finished_laps.partition_point(|lap| lap.start < start);
+        if let Some(lap) = index.checked_sub(1).and_then(|i| self.finished_laps.get(i)) {
+            if lap.end_time() > start {
+                return Err(LapError::Overlap { lap: index });
+            }
+        }
+        let next_start = match self.finished_laps.get(index) {
+            Some(lap) => Some(lap.start),
+            None => self.current_lap.as_ref().map(|lap| lap.normalize().start)
+        };
+        if matches!(next_start, Some(next_start) if next_start < end) {
+            return Err(LapError::Overlap { lap: index + 1 });
+        }
+        let mut lap = FinishedLap {
+            id: Uuid::new_v4(),
+            sw_id: self.identifier.id,
+            start,
+            duration,
+            segments: vec![Segment { start, end }],
+            label: None,
+            note: None
+        };
+        lap.annotate(annotation);
+        self.finished_laps.insert(index, lap);
+        Ok(index)
+    }
+
+    /// Find the index of a lap, which starts from 0 and includes the current
+    /// lap.
+    pub fn find_lap(&self, lap: LapRef) -> Result<usize, LapError> {
+        let laps_count = self.laps();
+        match lap {
+            LapRef::Number(number) if (1..=laps_count).contains(&number) => Ok(number - 1),
+            LapRef::Number(number) => Err(LapError::NoSuchLap { lap: number, laps_count }),
+            LapRef::Id(id) => self.finished_laps.iter()
+                .map(|lap| &lap.id)
+                .chain(self.current_lap.as_ref().map(|lap| &lap.id))
+                .enumerate()
+                .position(|(index, uuid)| lap.matches(index + 1, uuid))
+                .ok_or(LapError::UnknownLap { id })
+        }
+    }
+
+    /// Check that `index` refers to a lap that has finished.
+    fn check_finished(&self, index: usize) -> Result<(), LapError> {
+        let laps_count = self.laps();
+        if index >= laps_count {
+            Err(LapError::NoSuchLap { lap: index + 1, laps_count })
+        } else if index >= self.finished_laps.len() {
+            Err(LapError::Unfinished { lap: index + 1 })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Remove a lap that has finished. `index` starts from 0. The last lap
+    /// of a stopwatch can't be deleted.
+    pub fn delete_lap(&mut self, index: usize) -> Result<FinishedLap, LapError> {
+        self.check_finished(index)?;
+        if self.laps() == 1 {
+            return Err(LapError::OnlyLap);
+        }
+        Ok(self.finished_laps.remove(index))
+    }
+
+    /// Merge a lap with the lap after it, which can be the current lap.
+    /// `index` starts from 0.
+    pub fn merge_laps(&mut self, index: usize) -> Result<(), LapError> {
+        let laps_count = self.laps();
+        if index >= laps_count {
+            return Err(LapError::NoSuchLap { lap: index + 1, laps_count });
+        }
+        if index + 1 == laps_count {
+            return Err(LapError::NothingToMerge { lap: index + 1 });
+        }
+        // Every lap except the last one has finished
+        let previous = self.finished_laps.remove(index);
+        match (self.finished_laps.get_mut(index), self.current_lap.as_mut()) {
+            (Some(lap), _) => lap.merge_previous(previous),
+            (None, Some(lap)) => lap.merge_previous(previous),
+            (None, None) => unreachable!("lap {} had a lap after it", index + 1)
+        }
+        Ok(())
+    }
+
+    /// Split a lap that has finished into two laps after it has been playing
+    /// for `offset`. `index` starts from 0.
+    pub fn split_lap(&mut self, index: usize, offset: Duration) -> Result<(), LapError> {
+        self.check_finished(index)?;
+        match self.finished_laps[index].split(offset) {
+            Some(rest) => {
+                self.finished_laps.insert(index + 1, rest);
+                Ok(())
+            },
+            None => Err(LapError::BadSplit { lap: index + 1, offset })
+        }
+    }
+
+    /// Change how long a lap has been playing for. `index` starts from 0 and
+    /// includes the current lap.
+    pub fn set_lap_duration(&mut self, index: usize, duration: Duration) -> Result<(), LapError> {
+        let (_, current) = self.lap_start_and_duration(index)?;
+        let adjustment = if duration >= current {
+            TimeAdjustment::Add(duration - current)
+        } else {
+            TimeAdjustment::Subtract(current - duration)
+        };
+        self.adjust_lap(index, adjustment)
+    }
+
+    /// Terminate this stopwatch such that it cannot be played again. A
+    /// program that hasn't finished is aborted.
     pub fn end(&mut self) -> State {
         if let Some(prev_lap) = self.current_lap.take() {
+            if let Some(ref mut program) = self.program {
+                program.outcome.get_or_insert(ProgramOutcome::Aborted);
+            }
             let state = prev_lap.state();
             self.finished_laps.push(prev_lap.end());
             state
@@ -166,6 +689,53 @@ impl Stopwatch {
         }
     }
 
+    /// Let a stopwatch that has ended continue from a new lap. Returns the
+    /// [`State`] before reopening, so nothing has changed unless it is
+    /// [`State::Ended`].
+    pub fn reopen(&mut self, start_immediately: bool) -> State {
+        if self.current_lap.is_some() {
+            return self.state();
+        }
+        self.current_lap = Some(if start_immediately {
+            CurrentLap::start(self.identifier.id)
+        } else {
+            CurrentLap::new(self.identifier.id)
+        });
+        self.label_phase();
+        State::Ended
+    }
+
+    /// Throw away all laps and start again from a new lap, keeping the same
+    /// [`Identifier`]. This also works on a stopwatch that has ended.
+    ///
+    /// # Arguments
+    /// * `archive` - Keep the old laps as an [`ArchivedRun`].
+    /// * `start_immediately` - Whether the new lap should start playing.
+    pub fn reset(&mut self, archive: bool, start_immediately: bool) -> State {
+        let state = self.end();
+        let laps = std::mem::take(&mut self.finished_laps);
+        if archive {
+            self.archived_runs.push(ArchivedRun { reset_at: SystemTime::now(), laps });
+        }
+        self.current_lap = Some(if start_immediately {
+            CurrentLap::start(self.identifier.id)
+        } else {
+            CurrentLap::new(self.identifier.id)
+        });
+        if let Some(pomodoro) = self.pomodoro {
+            self.start_pomodoro(pomodoro.config);
+        }
+        if let Some(program) = self.program.take() {
+            self.start_program(program.program);
+        }
+        state
+    }
+
+    /// Runs from before the stopwatch was reset, oldest first.
+    pub fn archived_runs(&self) -> &[ArchivedRun] {
+        &self.archived_runs
+    }
+
     /// Get the current [`State`] of the stopwatch.
     pub fn state(&self) -> State {
         match &self.current_lap {
@@ -186,7 +756,7 @@ impl Stopwatch {
             None => Duration::new(0, 0)
         };
         self.finished_laps.iter()
-            .fold(total, |total, lap| total + lap.duration)
+            .fold(total, |total, lap| total.saturating_add(lap.duration))
     }
 
     /// Details about this stopwatch.
@@ -198,6 +768,367 @@ impl Stopwatch {
         report.push_str(&format!("    Duration: {} ms", self.total_time().as_millis()));
         report
     }
+
+    /// Take a serializable snapshot of this stopwatch.
+    pub fn snapshot(&self) -> StopwatchSnapshot {
+        StopwatchSnapshot {
+            schema_version: SCHEMA_VERSION,
+            identifier: self.identifier.clone(),
+            tags: self.tags.clone(),
+            finished_laps: self.finished_laps.clone(),
+            current_lap: self.current_lap.clone().map(Into::into),
+            archived_runs: self.archived_runs.clone(),
+            run: self.run.clone(),
+            goals: self.goals,
+            pomodoro: self.pomodoro,
+            program: self.program.clone(),
+            auto_lap: self.auto_lap
+        }
+    }
+}
+
+impl From<StopwatchSnapshot> for Stopwatch {
+    fn from(snapshot: StopwatchSnapshot) -> Self {
+        let StopwatchSnapshot {
+            identifier,
+            tags,
+            finished_laps,
+            current_lap,
+            archived_runs,
+            run,
+            goals,
+            pomodoro,
+            program,
+            auto_lap,
+            ..
+        } = snapshot;
+        let current_lap = current_lap.map(Into::into);
+        Self {
+            identifier,
+            tags,
+            finished_laps,
+            current_lap,
+            archived_runs,
+            run,
+            goals,
+            pomodoro,
+            program,
+            auto_lap
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{
+        error::LapError,
+        identifiers::{LapRef, Name},
+        models::{
+            auto_lap::AutoLap,
+            lap::{LapAnnotation, TimeAdjustment},
+            pomodoro::{Phase, PomodoroConfig},
+            program::{Program, ProgramOutcome, Step},
+            run::{Run, RunProgress}
+        }
+    };
+
+    use super::{Stopwatch, State};
+
+    /// Whether `duration` is within a second of `secs` seconds, for laps that
+    /// are timed from [`SystemTime::now`].
+    fn close_to(duration: Duration, secs: u64) -> bool {
+        duration.abs_diff(Duration::from_secs(secs)) < Duration::from_secs(1)
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut sw = Stopwatch::start(Name::fixed("sw"));
+        sw.new_lap(true);
+        sw.end();
+        let identifier = sw.identifier.clone();
+
+        assert_eq!(sw.reset(true, false), State::Ended);
+        assert_eq!(sw.identifier, identifier);
+        assert_eq!(sw.state(), State::Paused);
+        assert_eq!(sw.laps(), 1);
+        assert_eq!(sw.archived_runs().len(), 1);
+        assert_eq!(sw.archived_runs()[0].laps.len(), 2);
+
+        assert_eq!(sw.reset(false, true), State::Paused);
+        assert_eq!(sw.state(), State::Playing);
+        assert_eq!(sw.archived_runs().len(), 1);
+    }
+
+    #[test]
+    fn test_from_laps() {
+        let start = SystemTime::now() - Duration::from_secs(100);
+        let annotation = LapAnnotation { label: Some("a".to_string()), note: None };
+        let laps = [(Duration::from_secs(10), annotation), (Duration::from_secs(20), Default::default())];
+        let sw = Stopwatch::from_laps(Name::fixed("sw"), start, &laps).unwrap();
+        assert_eq!(sw.state(), State::Ended);
+        assert_eq!(sw.laps(), 2);
+        assert_eq!(sw.start_time(), Some(start));
+        assert_eq!(sw.total_time(), Duration::from_secs(30));
+        assert_eq!(sw.finished_laps()[0].label.as_deref(), Some("a"));
+        assert_eq!(sw.finished_laps()[1].start, start + Duration::from_secs(10));
+
+        assert!(Stopwatch::from_laps(Name::fixed("sw"), start, &[]).is_err());
+        let future = [(Duration::from_secs(1000), LapAnnotation::default())];
+        assert_eq!(Stopwatch::from_laps(Name::fixed("sw"), start, &future).err(), Some(LapError::FutureTime));
+        let overflow = [(Duration::from_secs(u64::MAX), LapAnnotation::default())];
+        assert_eq!(Stopwatch::from_laps(Name::fixed("sw"), start, &overflow).err(), Some(LapError::FutureTime));
+    }
+
+    #[test]
+    fn test_run() {
+        let mut sw = Stopwatch::start(Name::fixed("sw"));
+        sw.run = Some(RunProgress::from(&Run::new("any%", ["first", "second"])));
+        assert_eq!(sw.run_attempt().unwrap().segments.len(), 0);
+
+        assert_eq!(sw.new_lap(true), State::Playing);
+        assert_eq!(sw.run_attempt().unwrap().segments.len(), 1);
+        sw.pause();
+        // Finishing the last segment ends the stopwatch
+        assert_eq!(sw.new_lap(true), State::Paused);
+        assert_eq!(sw.state(), State::Ended);
+        assert_eq!(sw.laps(), 2);
+        assert_eq!(sw.run_attempt().unwrap().segments.len(), 2);
+    }
+
+    #[test]
+    fn test_pomodoro() {
+        let config = PomodoroConfig {
+            work: Duration::from_secs(100),
+            short_break: Duration::from_secs(20),
+            ..Default::default()
+        };
+        let now = SystemTime::now();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(130))
+            .unwrap();
+        sw.start_pomodoro(config);
+        // Work and the short break have both run out
+        assert_eq!(sw.check_pomodoro(), 2);
+        assert_eq!(sw.pomodoro.unwrap().phase, Phase::Work);
+        assert_eq!(sw.laps(), 3);
+        let labels: Vec<_> = sw.all_laps().into_iter().map(|lap| lap.label.unwrap()).collect();
+        assert_eq!(labels, ["work", "short break", "work"]);
+        assert!(close_to(sw.finished_laps()[0].duration, 100));
+        assert!(close_to(sw.finished_laps()[1].duration, 20));
+        assert_eq!(sw.check_pomodoro(), 0);
+
+        sw.pause();
+        assert_eq!(sw.next_phase(), Some(Phase::ShortBreak));
+        assert_eq!(sw.state(), State::Paused);
+        assert_eq!(sw.pomodoro.unwrap().cycles, 2);
+    }
+
+    #[test]
+    fn test_program() {
+        let step = |name: &str, secs| Step { name: name.to_string(), duration: Duration::from_secs(secs) };
+        let program = Program {
+            name: "tabata".to_string(),
+            steps: vec![step("work", 20), step("rest", 10), step("work", 20)]
+        };
+        let now = SystemTime::now();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(25))
+            .unwrap();
+        sw.start_program(program.clone());
+        assert_eq!(sw.check_program(), 1);
+        assert_eq!(sw.program.as_ref().unwrap().step, 1);
+        assert_eq!(sw.current_lap().unwrap().label.as_deref(), Some("rest"));
+        assert!(sw.next_step());
+        // Stopping before the last step finishes aborts the program
+        sw.end();
+        assert_eq!(sw.program.as_ref().unwrap().outcome, Some(ProgramOutcome::Aborted));
+        assert!(!sw.next_step());
+
+        // Running out of steps completes the program and ends the stopwatch
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(60))
+            .unwrap();
+        sw.start_program(program);
+        assert_eq!(sw.check_program(), 3);
+        assert_eq!(sw.state(), State::Ended);
+        assert_eq!(sw.laps(), 3);
+        assert_eq!(sw.program.as_ref().unwrap().outcome, Some(ProgramOutcome::Completed));
+        assert_eq!(sw.program.as_ref().unwrap().remaining_steps(), 0);
+        assert!(close_to(sw.total_time(), 50));
+
+        sw.reset(false, true);
+        assert_eq!(sw.program.as_ref().unwrap().outcome, None);
+        assert_eq!(sw.current_lap().unwrap().label.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_auto_lap() {
+        let now = SystemTime::now();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(25))
+            .unwrap();
+        assert_eq!(sw.check_auto_lap(), 0);
+        sw.auto_lap = Some(AutoLap::Every(Duration::from_secs(10)));
+        assert_eq!(sw.check_auto_lap(), 2);
+        assert_eq!(sw.laps(), 3);
+        let lap_time = sw.finished_laps()[1].duration;
+        assert!(close_to(lap_time, 10));
+
+        // Laps that started before midnight end at midnight
+        let two_days_ago = now - Duration::from_secs(2 * 86400);
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), two_days_ago).unwrap();
+        sw.auto_lap = Some(AutoLap::Daily);
+        assert!(sw.check_auto_lap() >= 2);
+        assert_eq!(sw.check_auto_lap(), 0);
+        let midnight = AutoLap::Daily.next_boundary(two_days_ago).unwrap();
+        assert_eq!(sw.finished_laps()[1].start, midnight);
+        assert!(sw.current_lap().unwrap().start <= now);
+
+        // Paused laps are left alone
+        sw.pause();
+        sw.auto_lap = Some(AutoLap::Every(Duration::ZERO));
+        assert_eq!(sw.check_auto_lap(), 0);
+    }
+
+    #[test]
+    fn test_reopen() {
+        let mut sw = Stopwatch::start(Name::fixed("sw"));
+        assert_eq!(sw.reopen(true), State::Playing);
+        assert_eq!(sw.laps(), 1);
+
+        sw.end();
+        assert_eq!(sw.reopen(false), State::Ended);
+        assert_eq!(sw.state(), State::Paused);
+        assert_eq!(sw.laps(), 2);
+        assert_eq!(sw.finished_laps().len(), 1);
+    }
+
+    #[test]
+    fn test_backdate() {
+        let now = SystemTime::now();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(300))
+            .unwrap();
+        assert_eq!(sw.start_time(), Some(now - Duration::from_secs(300)));
+        assert_eq!(sw.new_lap_at(now - Duration::from_secs(200)), Ok(State::Playing));
+        assert_eq!(sw.end_at(now - Duration::from_secs(100)), Ok(State::Playing));
+        // Instants and system times are read at slightly different moments
+        assert!(close_to(sw.finished_laps()[0].duration, 100));
+        assert!(close_to(sw.finished_laps()[1].duration, 100));
+
+        sw.adjust_lap(1, TimeAdjustment::Subtract(Duration::from_secs(60))).unwrap();
+        assert!(close_to(sw.total_time(), 140));
+        let too_much = TimeAdjustment::Subtract(Duration::from_secs(120));
+        assert_eq!(
+            sw.adjust_lap(0, too_much),
+            Err(LapError::BadAdjustment { lap: 1, adjustment: too_much })
+        );
+    }
+
+    #[test]
+    fn test_adjust_overflow() {
+        let now = SystemTime::now();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), now - Duration::from_secs(100))
+            .unwrap();
+        sw.start_pomodoro(PomodoroConfig::default());
+        let huge = TimeAdjustment::Add(Duration::from_secs(9_300_000_000_000_000_000));
+        assert_eq!(sw.adjust_lap(0, huge), Err(LapError::BadAdjustment { lap: 1, adjustment: huge }));
+        let too_long = TimeAdjustment::Add(Duration::from_secs(60));
+        assert_eq!(sw.adjust_lap(0, too_long), Err(LapError::BadAdjustment { lap: 1, adjustment: too_long }));
+        assert_eq!(sw.check_pomodoro(), 0);
+        assert!(close_to(sw.total_time(), 100));
+
+        // A lap that somehow got too long still ends instead of crashing
+        let mut snapshot = sw.snapshot();
+        snapshot.current_lap.as_mut().unwrap().duration = Duration::from_secs(u64::MAX / 2);
+        let mut sw = Stopwatch::from(snapshot);
+        assert_eq!(sw.check_pomodoro(), 1);
+        assert_eq!(sw.laps(), 2);
+    }
+
+    #[test]
+    fn test_backdate_paused_lap() {
+        let now = SystemTime::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), ago(600)).unwrap();
+        sw.pause_at(ago(500)).unwrap();
+        assert_eq!(sw.new_lap_at(ago(3000)), Err(LapError::TooEarly { earliest: ago(500) }));
+        assert_eq!(sw.laps(), 1);
+        assert_eq!(sw.new_lap_at(now + Duration::from_secs(60)), Err(LapError::FutureTime));
+
+        assert_eq!(sw.new_lap_at(ago(400)), Ok(State::Paused));
+        assert_eq!(sw.state(), State::Paused);
+        assert!(close_to(sw.finished_laps()[0].duration, 100));
+        let lap = sw.current_lap().unwrap();
+        assert_eq!(lap.start, ago(400));
+        assert_eq!(lap.total_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_modify_laps() {
+        let now = SystemTime::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+        let minute = Duration::from_secs(60);
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), ago(600)).unwrap();
+        sw.pause_at(ago(540)).unwrap();
+        sw.play_at(ago(480)).unwrap();
+        sw.new_lap_at(ago(420)).unwrap();
+        sw.new_lap_at(ago(300)).unwrap();
+        assert_eq!(sw.laps(), 3);
+
+        // Lap 1 played from 600 to 540 and from 480 to 420 seconds ago
+        sw.split_lap(0, Duration::from_secs(90)).unwrap();
+        assert_eq!(sw.laps(), 4);
+        let laps = sw.finished_laps();
+        assert_eq!(laps[0].duration, Duration::from_secs(90));
+        assert_eq!(laps[1].start, ago(450));
+        assert_eq!(laps[1].segments.len(), 1);
+        assert!(close_to(laps[1].duration, 30));
+        assert_eq!(sw.split_lap(1, minute), Err(LapError::BadSplit { lap: 2, offset: minute }));
+        assert_eq!(sw.split_lap(3, minute), Err(LapError::Unfinished { lap: 4 }));
+
+        let id = sw.finished_laps()[0].id;
+        sw.merge_laps(0).unwrap();
+        assert_eq!(sw.laps(), 3);
+        assert_eq!(sw.find_lap(LapRef::Id(id.into())), Ok(0));
+        assert!(close_to(sw.finished_laps()[0].duration, 120));
+
+        sw.set_lap_duration(1, minute).unwrap();
+        assert_eq!(sw.finished_laps()[1].duration, minute);
+        let deleted = sw.delete_lap(1).unwrap();
+        assert_eq!(deleted.duration, minute);
+        assert_eq!(sw.delete_lap(1), Err(LapError::Unfinished { lap: 2 }));
+
+        sw.merge_laps(0).unwrap();
+        assert_eq!(sw.laps(), 1);
+        assert_eq!(sw.current_lap().unwrap().id, id);
+        assert_eq!(sw.merge_laps(0), Err(LapError::NothingToMerge { lap: 1 }));
+        sw.end();
+        assert_eq!(sw.delete_lap(0), Err(LapError::OnlyLap));
+        assert_eq!(sw.find_lap(LapRef::Number(2)), Err(LapError::NoSuchLap { lap: 2, laps_count: 1 }));
+    }
+
+    #[test]
+    fn test_insert_lap() {
+        let now = SystemTime::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+        let minute = Duration::from_secs(60);
+        let annotation = LapAnnotation::default();
+        let mut sw = Stopwatch::start_at(Name::fixed("sw"), ago(300)).unwrap();
+        sw.pause_at(ago(200)).unwrap();
+
+        assert_eq!(sw.insert_lap(ago(400), minute, &annotation), Ok(0));
+        assert_eq!(sw.insert_lap(ago(330), minute, &annotation), Err(LapError::Overlap { lap: 2 }));
+        assert_eq!(sw.insert_lap(ago(390), minute, &annotation), Err(LapError::Overlap { lap: 1 }));
+        // The current lap started 300 seconds ago
+        assert_eq!(sw.insert_lap(ago(100), minute, &annotation), Err(LapError::Overlap { lap: 2 }));
+
+        sw.end();
+        assert_eq!(sw.insert_lap(ago(100), minute, &annotation), Ok(2));
+        assert_eq!(sw.insert_lap(ago(30), minute, &annotation), Err(LapError::FutureTime));
+        let forever = Duration::from_secs(u64::MAX);
+        assert_eq!(sw.insert_lap(ago(3600), forever, &annotation), Err(LapError::FutureTime));
+        let starts: Vec<_> = sw.finished_laps().iter().map(|lap| lap.start).collect();
+        assert_eq!(starts, vec![ago(400), ago(300), ago(100)]);
+        assert!(close_to(sw.total_time(), 220));
+    }
 }
 
 /*
@@ -0,0 +1,209 @@
+//! Countdown timers.
+
+use std::{
+    fmt,
+    time::{Duration, SystemTime}
+};
+
+use serde::{Serialize, Deserialize};
+
+use crate::identifiers::{Identifier, UniqueId, Name};
+
+use super::{
+    lap::CurrentLap,
+    snapshot::{SCHEMA_VERSION, TimerSnapshot},
+    stopwatch::State
+};
+
+/// How a [`Timer`] ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimerOutcome {
+    /// The timer ran for its whole target duration.
+    Expired,
+    /// The timer was stopped before it could expire.
+    Cancelled
+}
+
+impl fmt::Display for TimerOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TimerOutcome::*;
+        write!(f, "{}", match self {
+            Expired => "expired",
+            Cancelled => "cancelled"
+        })
+    }
+}
+
+/// Counts down from a target duration.
+///
+/// A [`Timer`] does not expire on its own, [`Timer::check_expiry`] has to be
+/// called to move an elapsed timer into the [`State::Ended`] state.
+///
+/// See [`TimerSnapshot`] to serialize a timer.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    pub identifier: Identifier,
+    /// How long the timer should run for.
+    pub target: Duration,
+    lap: CurrentLap,
+    outcome: Option<TimerOutcome>,
+    ended_at: Option<SystemTime>
+}
+
+impl Timer {
+    /// New timer with an optional name. The timer is paused by default.
+    pub fn new<N: Into<Name>>(name: N, target: Duration) -> Self {
+        let id = UniqueId::generate();
+        let identifier = Identifier::new(id, name.into());
+        let lap = CurrentLap::new(id);
+        Self { identifier, target, lap, outcome: None, ended_at: None }
+    }
+
+    /// New timer but start immediately.
+    pub fn start<N: Into<Name>>(name: N, target: Duration) -> Self {
+        let mut timer = Self::new(name, target);
+        timer.play();
+        timer
+    }
+
+    /// Continue counting down.
+    pub fn play(&mut self) -> State {
+        let state = self.state();
+        if !state.ended() {
+            self.lap.play();
+        }
+        state
+    }
+
+    /// Stop counting down temporarily.
+    pub fn pause(&mut self) -> State {
+        let state = self.state();
+        if !state.ended() {
+            self.lap.pause();
+        }
+        state
+    }
+
+    /// Stop the timer before it expires.
+    pub fn cancel(&mut self) -> State {
+        let state = self.state();
+        if !state.ended() {
+            self.lap.pause();
+            self.outcome = Some(TimerOutcome::Cancelled);
+            self.ended_at = Some(SystemTime::now());
+        }
+        state
+    }
+
+    /// End the timer if it has run for its target duration. Returns whether
+    /// the timer expired just now.
+    pub fn check_expiry(&mut self) -> bool {
+        if self.state().ended() || self.lap.total_time() < self.target {
+            return false;
+        }
+        self.lap.pause();
+        // The timer is only checked every so often, so work out when it
+        // actually expired.
+        let overshoot = self.lap.duration.saturating_sub(self.target);
+        self.lap.duration = self.target;
+        if let Some(segment) = self.lap.segments.last_mut() {
+            let end = segment.end.checked_sub(overshoot).unwrap_or(segment.start);
+            segment.end = end.max(segment.start);
+        }
+        self.outcome = Some(TimerOutcome::Expired);
+        self.ended_at = Some(SystemTime::now() - overshoot);
+        true
+    }
+
+    /// Get the current [`State`] of the timer.
+    pub fn state(&self) -> State {
+        if self.outcome.is_some() {
+            State::Ended
+        } else {
+            self.lap.state()
+        }
+    }
+
+    /// How the timer ended, if it has.
+    pub fn outcome(&self) -> Option<TimerOutcome> {
+        self.outcome
+    }
+
+    /// When the timer was created.
+    pub fn start_time(&self) -> SystemTime {
+        self.lap.start
+    }
+
+    /// When the timer expired or was cancelled.
+    pub fn ended_at(&self) -> Option<SystemTime> {
+        self.ended_at
+    }
+
+    /// How long the timer has been counting down for.
+    pub fn elapsed(&self) -> Duration {
+        self.lap.total_time().min(self.target)
+    }
+
+    /// Time left before the timer expires.
+    pub fn remaining(&self) -> Duration {
+        self.target.saturating_sub(self.lap.total_time())
+    }
+
+    /// Take a serializable snapshot of this timer.
+    pub fn snapshot(&self) -> TimerSnapshot {
+        TimerSnapshot {
+            schema_version: SCHEMA_VERSION,
+            identifier: self.identifier.clone(),
+            target: self.target,
+            lap: self.lap.clone().into(),
+            outcome: self.outcome,
+            ended_at: self.ended_at
+        }
+    }
+}
+
+impl From<TimerSnapshot> for Timer {
+    fn from(snapshot: TimerSnapshot) -> Self {
+        let TimerSnapshot { identifier, target, lap, outcome, ended_at, .. } = snapshot;
+        Self { identifier, target, lap: lap.into(), outcome, ended_at }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{identifiers::Name, models::stopwatch::State};
+
+    use super::{Timer, TimerOutcome};
+
+    #[test]
+    fn test_expire() {
+        let mut timer = Timer::start(Name::fixed("tea"), Duration::ZERO);
+        assert!(timer.check_expiry());
+        assert!(!timer.check_expiry());
+        assert_eq!(timer.state(), State::Ended);
+        assert_eq!(timer.outcome(), Some(TimerOutcome::Expired));
+        assert_eq!(timer.remaining(), Duration::ZERO);
+        assert_eq!(timer.elapsed(), Duration::ZERO);
+        assert!(timer.ended_at().is_some());
+    }
+
+    #[test]
+    fn test_paused_does_not_expire() {
+        let mut timer = Timer::new(Name::fixed("tea"), Duration::from_secs(60));
+        assert!(!timer.check_expiry());
+        assert_eq!(timer.state(), State::Paused);
+        assert_eq!(timer.remaining(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut timer = Timer::start(Name::fixed("tea"), Duration::from_secs(60));
+        assert_eq!(timer.cancel(), State::Playing);
+        assert_eq!(timer.cancel(), State::Ended);
+        assert_eq!(timer.play(), State::Ended);
+        assert!(!timer.check_expiry());
+        assert_eq!(timer.outcome(), Some(TimerOutcome::Cancelled));
+    }
+}
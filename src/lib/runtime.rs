@@ -10,6 +10,9 @@ use users::get_current_uid;
 /// Path to default system runtime directory.
 pub const SYSTEM_RUNTIME_PATH: &'static str = "/tmp/stopwatchd";
 
+/// Path to default system data directory, where `swd` saves its stopwatches.
+pub const SYSTEM_DATA_PATH: &str = "/var/lib/stopwatchd";
+
 /// Get uid of the current user.
 /// 
 /// If the `users` feature is enabled, then [`Some`] is returned containing the
@@ -73,3 +76,36 @@ pub fn runtime_dir(uid: Option<u32>) -> PathBuf {
         _ => PathBuf::from(SYSTEM_RUNTIME_PATH)
     }
 }
+
+/// Get data directory for `swd` being run by a non-root user.
+/// 
+/// This function tries the following directories in this order:
+///     1. `$XDG_DATA_HOME/stopwatchd`
+///     2. `$HOME/.local/share/stopwatchd`
+///     3. `fallback`, if provided
+///     4. `/var/tmp/stopwatchd/{uid}`
+#[cfg(feature = "users")]
+pub fn user_data_path(fallback: Option<String>, uid: u32) -> PathBuf {
+    PathBuf::from(env::var("XDG_DATA_HOME")
+        .or_else(|_| env::var("HOME").map(|s| s + "/.local/share"))
+        .map(|s| s + "/stopwatchd")
+        .ok()
+        .or(fallback)
+        .unwrap_or_else(|| format!("/var/tmp/stopwatchd/{}", uid)))
+}
+
+/// Get data directory.
+/// 
+/// If the `users` feature is enabled, and `uid` is non-zero (i.e. not root),
+/// then a user data directory is returned.
+/// 
+/// Otherwise the system data directory is used.
+/// 
+/// `uid` can be taken from [`get_uid`].
+pub fn data_dir(uid: Option<u32>) -> PathBuf {
+    match uid {
+        #[cfg(feature = "users")]
+        Some(uid) if uid != 0 => user_data_path(None, uid),
+        _ => PathBuf::from(SYSTEM_DATA_PATH)
+    }
+}